@@ -23,33 +23,50 @@ CREATE TABLE IF NOT EXISTS proofs (
   cert_hash TEXT,
   trace_hash TEXT
 );
+ALTER TABLE proofs ADD COLUMN IF NOT EXISTS pack_version TEXT;
+ALTER TABLE proofs ADD COLUMN IF NOT EXISTS witness JSONB;
+ALTER TABLE proofs ADD COLUMN IF NOT EXISTS robustness DOUBLE PRECISION;
+ALTER TABLE proofs ADD COLUMN IF NOT EXISTS correction BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE proofs ADD COLUMN IF NOT EXISTS asset TEXT;
 """
 type_defs = """
 type Proof {
   id: ID!
   propertyId: String!
+  "Asset (PLC, station, segment) whose trace was judged."
+  asset: String
   startTs: String!
   endTs:   String!
   verdict: String!
   certHash: String!
   traceHash: String!
+  packVersion: String
+  "Counterexample of a FAIL verdict, as JSON."
+  witness: String
+  "Signed margin from flipping the verdict; null when vacuous."
+  robustness: Float
+  "Revises an earlier verdict after a late sample."
+  correction: Boolean!
 }
 
 type Query {
-  proofs(propertyId: String, from: String, to: String): [Proof!]!
+  proofs(propertyId: String, asset: String, from: String, to: String): [Proof!]!
 }
 """
 
 query = QueryType()
 
 @query.field("proofs")
-async def resolve_proofs(*_, propertyId=None, **range_):
+async def resolve_proofs(*_, propertyId=None, asset=None, **range_):
     async with db_pool.acquire() as con:
         sql = "SELECT * FROM proofs WHERE 1=1"
         args = []
         if propertyId:
-            sql += " AND property_id = $1"
             args.append(propertyId)
+            sql += f" AND property_id = ${len(args)}"
+        if asset:
+            args.append(asset)
+            sql += f" AND asset = ${len(args)}"
         if range_.get("from"):
             args.append(dt.datetime.fromisoformat(range_["from"]))
             sql += f" AND start_ts >= ${len(args)}"
@@ -59,11 +76,13 @@ async def resolve_proofs(*_, propertyId=None, **range_):
         rows = await con.fetch(sql, *args)
         return [
             dict(
-              id=r["id"], propertyId=r["property_id"],
+              id=r["id"], propertyId=r["property_id"], asset=r["asset"],
               startTs=r["start_ts"].isoformat(),
               endTs=r["end_ts"].isoformat(),
               verdict=r["verdict"], certHash=r["cert_hash"],
-              traceHash=r["trace_hash"]
+              traceHash=r["trace_hash"], packVersion=r["pack_version"],
+              witness=r["witness"], robustness=r["robustness"],
+              correction=r["correction"]
             )
             for r in rows
         ]
@@ -74,22 +93,31 @@ app.mount("/graphql", GraphQL(schema, debug=False))
 
 # ---------- Background consumer ------------------------------------------
 async def kafka_consumer():
+    # read_committed: packets of aborted proof-engine transactions never land
     consumer = aiokafka.AIOKafkaConsumer(
-        PROOF_TOPIC, bootstrap_servers=KAFKA, value_deserializer=lambda b: json.loads(b.decode()))
+        PROOF_TOPIC, bootstrap_servers=KAFKA, isolation_level="read_committed",
+        value_deserializer=lambda b: json.loads(b.decode()))
     await consumer.start()
     try:
         async for msg in consumer:
             p = msg.value
+            if "event" in p:   # e.g. PACK_VERSION_CHANGED, not a proof packet
+                continue
             async with db_pool.acquire() as con:
                 await con.execute(
-                    "INSERT INTO proofs(property_id,start_ts,end_ts,verdict,cert_hash,trace_hash)"
-                    "VALUES($1,$2,$3,$4,$5,$6)",
+                    "INSERT INTO proofs(property_id,start_ts,end_ts,verdict,cert_hash,trace_hash,pack_version,witness,robustness,correction,asset)"
+                    "VALUES($1,$2,$3,$4,$5,$6,$7,$8,$9,$10,$11)",
                     p["property_id"],
                     dt.datetime.fromtimestamp(p["start_ts"]),
                     dt.datetime.fromtimestamp(p["end_ts"]),
                     p["verdict"],
                     p["cert_hash"],
-                    p["trace_hash"])
+                    p["trace_hash"],
+                    p.get("pack_version"),
+                    json.dumps(p["witness"]) if "witness" in p else None,
+                    p.get("robustness"),
+                    p.get("correction", False),
+                    p.get("asset"))
     finally:
         await consumer.stop()
 
@@ -1,17 +1,123 @@
 //! Modbus-to-Kafka Edge Agent.
+//!
+//! The register → tag map is read from the JSON file named by `EDGE_TAG_MAP`
+//! (`[{"register": 0, "tag": "P", "scale": 1.0}, …]`); without it the agent
+//! polls the four demo registers `P, T, Flow, Valve`.
+//!
+//! Records are keyed by `ASSET_ID` (default: the PLC host), which the
+//! proof-engine uses to keep one trace per asset; the id is also in each
+//! payload, so sinks without keys keep it.
+//!
+//! `TRACE_SINK` picks where records go: `kafka` (default), `stdio` for
+//! JSON lines on stdout, or `file:<path>` to append them to a file.
 
 use chrono::Utc;
 use rdkafka::config::ClientConfig;
 use rdkafka::producer::{FutureProducer, FutureRecord};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::time::Duration;
+use tokio::io::{AsyncWrite, AsyncWriteExt};
 use tokio_modbus::prelude::*;
 
 #[derive(Serialize)]
 struct TracePacket<'a> {
-    ts:   i64,
-    tags: &'a HashMap<&'static str, f64>,
+    /// Unix seconds, millisecond resolution.
+    ts:    f64,
+    asset: &'a str,
+    tags:  &'a HashMap<&'a str, f64>,
+}
+
+/// Where trace records go.
+enum TraceSink {
+    Kafka { producer: FutureProducer, topic: String },
+    /// JSON lines, one record each.
+    Jsonl(Box<dyn AsyncWrite + Unpin + Send>),
+}
+
+impl TraceSink {
+    async fn from_env() -> anyhow::Result<Self> {
+        let sink = std::env::var("TRACE_SINK").unwrap_or_else(|_| "kafka".into());
+        Ok(match sink.as_str() {
+            "kafka" => {
+                let kafka = std::env::var("KAFKA_BROKERS").unwrap_or_else(|_| "localhost:9092".into());
+                let topic = std::env::var("KAFKA_TRACE_TOPIC").unwrap_or_else(|_| "plc.trace".into());
+                let producer = ClientConfig::new().set("bootstrap.servers", &kafka).create()?;
+                TraceSink::Kafka { producer, topic }
+            }
+            "stdio" | "-" => TraceSink::Jsonl(Box::new(tokio::io::stdout())),
+            _ => match sink.strip_prefix("file:") {
+                Some(path) if !path.is_empty() => {
+                    let file = tokio::fs::OpenOptions::new().create(true).append(true).open(path).await?;
+                    TraceSink::Jsonl(Box::new(file))
+                }
+                _ => anyhow::bail!("TRACE_SINK={sink:?}: expected `kafka`, `stdio` or `file:<path>`"),
+            },
+        })
+    }
+
+    async fn send(&mut self, asset: &str, bytes: &[u8]) -> anyhow::Result<()> {
+        match self {
+            TraceSink::Kafka { producer, topic } => {
+                producer
+                    .send(FutureRecord::to(topic).key(asset).payload(bytes), Duration::from_secs(0))
+                    .await
+                    .map_err(|(e, _msg)| e)?;   // convert tuple → KafkaError
+            }
+            TraceSink::Jsonl(out) => {
+                out.write_all(bytes).await?;
+                out.write_all(b"\n").await?;
+                out.flush().await?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// One polled holding register.
+#[derive(Deserialize)]
+struct TagCfg {
+    register: u16,
+    tag:      String,
+    #[serde(default = "unit_scale")]
+    scale:    f64,
+}
+
+fn unit_scale() -> f64 { 1.0 }
+
+/// Modbus caps a single holding-register read at 125 registers.
+const MAX_READ: u16 = 125;
+
+/// `(start, count)` holding-register reads covering every configured
+/// register, each at most `MAX_READ` long; gaps wider than that are skipped.
+fn read_spans(cfg: &[TagCfg]) -> Vec<(u16, u16)> {
+    let mut regs: Vec<u16> = cfg.iter().map(|c| c.register).collect();
+    regs.sort_unstable();
+    let mut spans: Vec<(u16, u16)> = Vec::new();
+    for r in regs {
+        match spans.last_mut() {
+            Some((start, n)) if r - *start < MAX_READ => *n = r - *start + 1,
+            _ => spans.push((r, 1)),
+        }
+    }
+    spans
+}
+
+fn load_tag_map() -> anyhow::Result<Vec<TagCfg>> {
+    let Ok(path) = std::env::var("EDGE_TAG_MAP") else {
+        // (register idx, tag name, scale factor)
+        return Ok([(0, "P", 1.0), (1, "T", 1.0), (2, "Flow", 0.1), (3, "Valve", 1.0)]
+            .into_iter()
+            .map(|(register, tag, scale)| TagCfg { register, tag: tag.into(), scale })
+            .collect());
+    };
+    let cfg: Vec<TagCfg> = serde_json::from_str(&std::fs::read_to_string(&path)?)?;
+    anyhow::ensure!(!cfg.is_empty(), "{path}: empty tag map");
+    let mut seen = std::collections::HashSet::new();
+    for c in &cfg {
+        anyhow::ensure!(seen.insert(c.tag.as_str()), "{path}: duplicate tag {:?}", c.tag);
+    }
+    Ok(cfg)
 }
 
 #[tokio::main]
@@ -20,42 +126,36 @@ async fn main() -> anyhow::Result<()> {
     env_logger::init();
 
     let plc_addr = std::env::var("PLC_HOST").unwrap_or_else(|_| "127.0.0.1".into());
-    let kafka    = std::env::var("KAFKA_BROKERS").unwrap_or_else(|_| "localhost:9092".into());
-    let topic    = std::env::var("KAFKA_TRACE_TOPIC").unwrap_or_else(|_| "plc.trace".into());
+    let asset    = std::env::var("ASSET_ID").unwrap_or_else(|_| plc_addr.clone());
 
-    let producer: FutureProducer =
-        ClientConfig::new().set("bootstrap.servers", &kafka).create()?;
+    let mut sink = TraceSink::from_env().await?;
 
     let sock = format!("{plc_addr}:502").parse()?;
     let mut ctx = tcp::connect(sock).await?;
 
-    // (register idx, tag name, scale factor)
-    let cfg = vec![
-        (0, "P", 1.0),
-        (1, "T", 1.0),
-        (2, "Flow", 0.1),
-        (3, "Valve", 1.0),
-    ];
+    let cfg = load_tag_map()?;
+    let spans = read_spans(&cfg);
+    log::info!("polling {} tags in {} reads as asset {asset:?}", cfg.len(), spans.len());
 
     loop {
-        // 1. Read registers
-        let regs = ctx.read_holding_registers(0, cfg.len() as u16).await?;
+        // 1. Read registers (one request per span)
+        let mut regs = HashMap::with_capacity(cfg.len());
+        for &(start, n) in &spans {
+            let values = ctx.read_holding_registers(start, n).await?;
+            regs.extend(values.into_iter().enumerate().map(|(i, v)| (usize::from(start) + i, v)));
+        }
         let mut map = HashMap::with_capacity(cfg.len());
-        for ((_, tag, scale), raw) in cfg.iter().zip(regs) {
-            map.insert(*tag, (raw as f64) * scale);
+        for c in &cfg {
+            let Some(&raw) = regs.get(&usize::from(c.register)) else {
+                anyhow::bail!("short Modbus read: no value for register {} ({})", c.register, c.tag);
+            };
+            map.insert(c.tag.as_str(), (raw as f64) * c.scale);
         }
 
         // 2. Serialize + send
-        let pkt   = TracePacket { ts: Utc::now().timestamp(), tags: &map };
+        let pkt   = TracePacket { ts: Utc::now().timestamp_millis() as f64 / 1e3, asset: &asset, tags: &map };
         let bytes = serde_json::to_vec(&pkt)?;
-
-        producer
-            .send(
-                FutureRecord::<(), _>::to(&topic).payload(&bytes),
-                Duration::from_secs(0),
-            )
-            .await
-            .map_err(|(e, _msg)| e)?;   // convert tuple → KafkaError
+        sink.send(&asset, &bytes).await?;
 
         tokio::time::sleep(Duration::from_secs(5)).await;
     }
@@ -8,6 +8,18 @@ Fully checked implementation **v0.2** (26 Jun 2025)
 * **NEW:** Appends `test/PropSoundSpec.lean`, a Lake test script performing 1 000 randomized checks that `eval p τ = true` never occurs when `holdsBool p τ = false`.
 
 Both files compile on Lean 4.5 / mathlib4 nightly 25-06-2025.
+
+**Scope.** `holds` and `eval` are two-valued and read an absent tag as 0
+(`findD v 0`).  The proof-engine publishes PASS / FAIL / INCONCLUSIVE from
+the Kleene evaluator `dsl::eval_truth`, which is *not* modelled here:
+`eval_sound` covers a published verdict only on a complete window – every
+sample carrying every tag the property reads – where `eval_truth` and this
+`eval` agree (checked in `dsl.rs`, `missing_readings_are_unknown_not_zero`).
+Verdicts on windows with missing readings, and the `missing` policies, are
+outside the proof.  So is real time: `rateBound v k` here bounds the raw
+step `|Δv| ≤ k`, while the engine bounds it per second of sample time
+(`k · Δt`).  The two agree only when the samples are 1 s apart, so a
+verdict on a property with `|dX|` atoms is covered only on such a window.
 -/-
 
 import Std.Data.HashMap
@@ -23,11 +35,18 @@ inductive Var | P | T | Flow | Valve deriving DecidableEq, Repr, Hashable
 /-- Sentinel constraint DSL. -/
 inductive Prop
 | le        : Var → ℚ → Prop
+| ge        : Var → ℚ → Prop
+| lt        : Var → ℚ → Prop
+| gt        : Var → ℚ → Prop
+| eq        : Var → ℚ → ℚ → Prop   -- value, tolerance
+| ne        : Var → ℚ → ℚ → Prop   -- value, tolerance
 | rateBound : Var → ℚ → Prop
 | windowAll : Nat → Prop → Prop
 | implWithin : Prop → Prop → Nat → Prop
 | and       : Prop → Prop → Prop
 | or        : Prop → Prop → Prop
+| not       : Prop → Prop
+| implies   : Prop → Prop → Prop
   deriving Repr
 
 abbrev Sample := Std.HashMap Var ℚ
@@ -38,6 +57,16 @@ open Prop
 noncomputable def holds : Prop → Trace → Prop
 | le v k, (s :: _)   => s.findD v 0 ≤ k
 | le _ _,  []        => True
+| ge v k, (s :: _)   => k ≤ s.findD v 0
+| ge _ _,  []        => True
+| lt v k, (s :: _)   => s.findD v 0 < k
+| lt _ _,  []        => True
+| gt v k, (s :: _)   => k < s.findD v 0
+| gt _ _,  []        => True
+| eq v k tol, (s :: _) => |s.findD v 0 - k| ≤ tol
+| eq _ _ _, []       => True
+| ne v k tol, (s :: _) => tol < |s.findD v 0 - k|
+| ne _ _ _, []       => True
 | rateBound v k, (s₂ :: s₁ :: _) =>
     |(s₂.findD v 0 - s₁.findD v 0)| ≤ k
 | rateBound _ _, _   => True
@@ -49,15 +78,28 @@ noncomputable def holds : Prop → Trace → Prop
     holds p τ → ∃ n, n ≤ k ∧ holds q (τ.drop n)
 | and p q, τ                     => holds p τ ∧ holds q τ
 | or  p q, τ                     => holds p τ ∨ holds q τ
+| not p, τ                       => ¬ holds p τ
+| implies p q, τ                 => holds p τ → holds q τ
 
 /-- Helper: `decide` → Prop. -/
 lemma of_decide_eq_true {α} [Decidable α] {h : decide α = true} : α := by
   simpa using (decide_eq_true_iff.mp h)
 
-/-- Executable monitor. -/
+/-- Executable monitor.  Two-valued: exact for the proof-engine only on
+    complete windows (see **Scope** above). -/
 noncomputable def eval : Prop → Trace → Bool
 | le v k, (s :: _)   => decide (s.findD v 0 ≤ k)
 | le _ _,  []        => true
+| ge v k, (s :: _)   => decide (k ≤ s.findD v 0)
+| ge _ _,  []        => true
+| lt v k, (s :: _)   => decide (s.findD v 0 < k)
+| lt _ _,  []        => true
+| gt v k, (s :: _)   => decide (k < s.findD v 0)
+| gt _ _,  []        => true
+| eq v k tol, (s :: _) => decide (|s.findD v 0 - k| ≤ tol)
+| eq _ _ _, []       => true
+| ne v k tol, (s :: _) => decide (tol < |s.findD v 0 - k|)
+| ne _ _ _, []       => true
 | rateBound v k, (s₂ :: s₁ :: _) =>
     decide (|(s₂.findD v 0 - s₁.findD v 0)| ≤ k)
 | rateBound _ _, _               => true
@@ -72,6 +114,8 @@ noncomputable def eval : Prop → Trace → Bool
       true
 | and p q, τ                     => eval p τ && eval q τ
 | or  p q, τ                     => eval p τ || eval q τ
+| not p, τ                       => !eval p τ
+| implies p q, τ                 => !eval p τ || eval q τ
 
 /-- Bool helper lemmas. -/
 lemma Bool.and_eq_true {a b : Bool} : a && b = true ↔ a = true ∧ b = true := by
@@ -98,52 +142,57 @@ lemma List.any_eq_true {α} {l : List α} {p : α → Bool}
               | head => exact Or.inl hp
               | tail ha => exact Or.inr ⟨a, ha, hp⟩
 
-/-- Main soundness theorem. -/
+/-- Main correctness theorem.  `not`/`implies` flip polarity, so soundness
+    alone no longer carries the induction: we prove `eval` and `holds` agree. -/
 open Classical
-noncomputable def eval_sound : ∀ p τ, eval p τ = true → holds p τ
-| le v k, τ, h => by
-    cases τ with
-    | nil => trivial
-    | cons s _ =>
-        dsimp [eval] at h
-        simpa [holds] using (of_decide_eq_true h)
-| rateBound v k, τ, h => by
+noncomputable def eval_iff : ∀ p τ, eval p τ = true ↔ holds p τ
+| le v k, τ => by cases τ <;> simp [eval, holds]
+| ge v k, τ => by cases τ <;> simp [eval, holds]
+| lt v k, τ => by cases τ <;> simp [eval, holds]
+| gt v k, τ => by cases τ <;> simp [eval, holds]
+| eq v k tol, τ => by cases τ <;> simp [eval, holds]
+| ne v k tol, τ => by cases τ <;> simp [eval, holds]
+| rateBound v k, τ => by
     cases τ with
-    | nil => trivial
-    | cons _ tl =>
-        cases tl with
-        | nil => trivial
-        | cons s₁ tl' =>
-            dsimp [eval] at h
-            simpa [holds] using (of_decide_eq_true h)
-| windowAll 0 p, τ, h => eval_sound p τ h
-| windowAll (Nat.succ k) p, τ, h => by
+    | nil => simp [eval, holds]
+    | cons _ tl => cases tl <;> simp [eval, holds]
+| windowAll 0 p, τ => by simpa [eval, holds] using eval_iff p τ
+| windowAll (Nat.succ k) p, τ => by
     cases τ with
-    | nil => trivial
-    | cons _ rest =>
-        dsimp [eval] at h
-        have := (Bool.and_eq_true).mp h
-        exact ⟨eval_sound p _ this.1, eval_sound (windowAll k p) _ this.2⟩
-| implWithin p q k, τ, h => by
-    dsimp [eval] at h
+    | nil => simp [eval, holds]
+    | cons s rest =>
+        simp [eval, holds, Bool.and_eq_true, eval_iff p (s :: rest),
+              eval_iff (windowAll k p) rest]
+| implWithin p q k, τ => by
     by_cases hp : eval p τ
-    · have hpHolds : holds p τ := eval_sound p τ hp
-      have hAny : (List.range (k + 1)).any (fun n => eval q (τ.drop n)) = true := by
-        simpa [hp] using h
-      have ⟨n, hnMem, hq⟩ := (List.any_eq_true).1 hAny
-      have hn : n ≤ k := by
-        have : n ∈ List.range (k + 1) := hnMem; simpa [List.mem_range] using this
-      have hqHolds : holds q (τ.drop n) := eval_sound q _ hq
-      exact (fun hpτ => ⟨n, hn, hqHolds⟩) hpHolds
-    · simpa [hp] using h
-| and p q, τ, h => by
-    have := (Bool.and_eq_true).mp h
-    exact And.intro (eval_sound p _ this.1) (eval_sound q _ this.2)
-| or p q, τ, h => by
-    have := (Bool.or_eq_true).mp h
-    cases this with
-    | inl hp => exact Or.inl (eval_sound p _ hp)
-    | inr hq => exact Or.inr (eval_sound q _ hq)
+    · have hpHolds : holds p τ := (eval_iff p τ).mp hp
+      simp only [eval, hp, if_true, holds]
+      constructor
+      · intro hAny _
+        have ⟨n, hnMem, hq⟩ := (List.any_eq_true).1 hAny
+        have hn : n ≤ k := by simpa [List.mem_range, Nat.lt_succ_iff] using hnMem
+        exact ⟨n, hn, (eval_iff q _).mp hq⟩
+      · intro h
+        have ⟨n, hn, hq⟩ := h hpHolds
+        exact (List.any_eq_true).2
+          ⟨n, by simpa [List.mem_range, Nat.lt_succ_iff] using hn, (eval_iff q _).mpr hq⟩
+    · have hpNot : ¬ holds p τ := fun h => hp ((eval_iff p τ).mpr h)
+      simp [eval, hp, holds, hpNot]
+| and p q, τ => by
+    simp [eval, holds, Bool.and_eq_true, eval_iff p τ, eval_iff q τ]
+| or p q, τ => by
+    simp [eval, holds, Bool.or_eq_true, eval_iff p τ, eval_iff q τ]
+| not p, τ => by
+    simp [eval, holds, ← eval_iff p τ]
+| implies p q, τ => by
+    by_cases hp : eval p τ
+    · simp [eval, holds, hp, (eval_iff p τ).mp hp, eval_iff q τ]
+    · have hpNot : ¬ holds p τ := fun h => hp ((eval_iff p τ).mpr h)
+      simp [eval, holds, hp, hpNot]
+
+/-- Main soundness theorem. -/
+noncomputable def eval_sound : ∀ p τ, eval p τ = true → holds p τ :=
+  fun p τ h => (eval_iff p τ).mp h
 
 @[simp] theorem eval_sound_global {p τ} (h : eval p τ = true) : holds p τ := eval_sound p τ h
 
@@ -161,6 +210,16 @@ open Sentinel
 noncomputable def holdsBool : Prop → Trace → Bool
 | Prop.le v k, (s :: _)   => decide (s.findD v 0 ≤ k)
 | Prop.le _ _, []         => true
+| Prop.ge v k, (s :: _)   => decide (k ≤ s.findD v 0)
+| Prop.ge _ _, []         => true
+| Prop.lt v k, (s :: _)   => decide (s.findD v 0 < k)
+| Prop.lt _ _, []         => true
+| Prop.gt v k, (s :: _)   => decide (k < s.findD v 0)
+| Prop.gt _ _, []         => true
+| Prop.eq v k tol, (s :: _) => decide (|s.findD v 0 - k| ≤ tol)
+| Prop.eq _ _ _, []       => true
+| Prop.ne v k tol, (s :: _) => decide (tol < |s.findD v 0 - k|)
+| Prop.ne _ _ _, []       => true
 | Prop.rateBound v k, (s₂ :: s₁ :: _) =>
     decide (|(s₂.findD v 0 - s₁.findD v 0)| ≤ k)
 | Prop.rateBound _ _, _   => true
@@ -174,6 +233,8 @@ noncomputable def holdsBool : Prop → Trace → Bool
     else true
 | Prop.and p q, τ         => holdsBool p τ && holdsBool q τ
 | Prop.or  p q, τ         => holdsBool p τ || holdsBool q τ
+| Prop.not p, τ           => !holdsBool p τ
+| Prop.implies p q, τ     => !holdsBool p τ || holdsBool q τ
 
 /-- Random generation utilities. -/
 open IO
@@ -194,13 +255,23 @@ private def randSample : IO Sample := do
       let val ← randRat; s := s.insert v val
   pure s
 
+private def randAtom : IO Prop := do
+  let v ← randVar; let k ← randRat
+  let choice ← IO.rand 0 5
+  match choice with
+  | 0 => pure (Prop.le v k)
+  | 1 => pure (Prop.ge v k)
+  | 2 => pure (Prop.lt v k)
+  | 3 => pure (Prop.gt v k)
+  | 4 => let tol ← IO.rand 0 2; pure (Prop.eq v k tol)
+  | _ => let tol ← IO.rand 0 2; pure (Prop.ne v k tol)
+
 private partial def randProp (depth : Nat) : IO Prop :=
   if depth = 0 then
-    do
-      let v ← randVar; let k ← randRat; pure (Prop.le v k)
+    randAtom
   else
     do
-      let choice ← IO.rand 0 5
+      let choice ← IO.rand 0 7
       match choice with
       | 0 => randProp 0
       | 1 => let v ← randVar; let k ← randRat; pure (Prop.rateBound v k)
@@ -212,8 +283,12 @@ private partial def randProp (depth : Nat) : IO Prop :=
           let k ← IO.rand 0 5; pure (Prop.implWithin p q k)
       | 4 =>
           let p ← randProp (depth - 1); let q ← randProp (depth - 1); pure (Prop.and p q)
-      | _ =>
+      | 5 =>
           let p ← randProp (depth - 1); let q ← randProp (depth - 1); pure (Prop.or p q)
+      | 6 =>
+          let p ← randProp (depth - 1); pure (Prop.not p)
+      | _ =>
+          let p ← randProp (depth - 1); let q ← randProp (depth - 1); pure (Prop.implies p q)
 
 private def randTrace : IO Trace := do
   let len ← IO.rand 0 6
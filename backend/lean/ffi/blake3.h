@@ -5,85 +5,82 @@
 #include <stdint.h>
 
 #if !defined(BLAKE3_API)
-#if defined(_WIN32) || defined(__CYGWIN__)
-#if defined(BLAKE3_DLL)
-#if defined(BLAKE3_DLL_EXPORTS)
-#define BLAKE3_API __declspec(dllexport)
-#else
-#define BLAKE3_API __declspec(dllimport)
-#endif
-#define BLAKE3_PRIVATE
-#else
-#define BLAKE3_API
-#define BLAKE3_PRIVATE
-#endif
-#elif __GNUC__ >= 4
-#define BLAKE3_API __attribute__((visibility("default")))
-#define BLAKE3_PRIVATE __attribute__((visibility("hidden")))
-#else
-#define BLAKE3_API
-#define BLAKE3_PRIVATE
-#endif
+# if defined(_WIN32) || defined(__CYGWIN__)
+#   if defined(BLAKE3_DLL)
+#     if defined(BLAKE3_DLL_EXPORTS)
+#       define BLAKE3_API __declspec(dllexport)
+#     else
+#       define BLAKE3_API __declspec(dllimport)
+#     endif
+#     define BLAKE3_PRIVATE
+#   else
+#     define BLAKE3_API
+#     define BLAKE3_PRIVATE
+#   endif
+# elif __GNUC__ >= 4
+#   define BLAKE3_API __attribute__((visibility("default")))
+#   define BLAKE3_PRIVATE __attribute__((visibility("hidden")))
+# else
+#   define BLAKE3_API
+#   define BLAKE3_PRIVATE
+# endif
 #endif
 
 #ifdef __cplusplus
-extern "C"
-{
+extern "C" {
 #endif
 
-#define BLAKE3_VERSION_STRING "1.8.2"
+#define BLAKE3_VERSION_STRING "1.8.7"
 #define BLAKE3_KEY_LEN 32
 #define BLAKE3_OUT_LEN 32
 #define BLAKE3_BLOCK_LEN 64
 #define BLAKE3_CHUNK_LEN 1024
 #define BLAKE3_MAX_DEPTH 54
 
-    // This struct is a private implementation detail. It has to be here because
-    // it's part of blake3_hasher below.
-    typedef struct
-    {
-        uint32_t cv[8];
-        uint64_t chunk_counter;
-        uint8_t buf[BLAKE3_BLOCK_LEN];
-        uint8_t buf_len;
-        uint8_t blocks_compressed;
-        uint8_t flags;
-    } blake3_chunk_state;
+// This struct is a private implementation detail. It has to be here because
+// it's part of the blake3_hasher structure defined below.
+typedef struct {
+  uint32_t cv[8];
+  uint64_t chunk_counter;
+  uint8_t buf[BLAKE3_BLOCK_LEN];
+  uint8_t buf_len;
+  uint8_t blocks_compressed;
+  uint8_t flags;
+} blake3_chunk_state;
 
-    typedef struct
-    {
-        uint32_t key[8];
-        blake3_chunk_state chunk;
-        uint8_t cv_stack_len;
-        // The stack size is MAX_DEPTH + 1 because we do lazy merging. For example,
-        // with 7 chunks, we have 3 entries in the stack. Adding an 8th chunk
-        // requires a 4th entry, rather than merging everything down to 1, because we
-        // don't know whether more input is coming. This is different from how the
-        // reference implementation does things.
-        uint8_t cv_stack[(BLAKE3_MAX_DEPTH + 1) * BLAKE3_OUT_LEN];
-    } blake3_hasher;
+typedef struct {
+  uint32_t key[8];
+  blake3_chunk_state chunk;
+  uint8_t cv_stack_len;
+  // The stack size is MAX_DEPTH + 1 because we do lazy merging. For example,
+  // with 7 chunks, we have 3 entries in the stack. Adding an 8th chunk
+  // requires a 4th entry, rather than merging everything down to 1, because we
+  // don't know whether more input is coming. This is different from how the
+  // reference implementation does things.
+  uint8_t cv_stack[(BLAKE3_MAX_DEPTH + 1) * BLAKE3_OUT_LEN];
+} blake3_hasher;
 
-    BLAKE3_API const char *blake3_version(void);
-    BLAKE3_API void blake3_hasher_init(blake3_hasher *self);
-    BLAKE3_API void blake3_hasher_init_keyed(blake3_hasher *self,
-                                             const uint8_t key[BLAKE3_KEY_LEN]);
-    BLAKE3_API void blake3_hasher_init_derive_key(blake3_hasher *self, const char *context);
-    BLAKE3_API void blake3_hasher_init_derive_key_raw(blake3_hasher *self, const void *context,
-                                                      size_t context_len);
-    BLAKE3_API void blake3_hasher_update(blake3_hasher *self, const void *input,
-                                         size_t input_len);
+BLAKE3_API const char *blake3_version(void);
+BLAKE3_API void blake3_hasher_init(blake3_hasher *self);
+BLAKE3_API void blake3_hasher_init_keyed(blake3_hasher *self,
+                                         const uint8_t key[BLAKE3_KEY_LEN]);
+BLAKE3_API void blake3_hasher_init_derive_key(blake3_hasher *self, const char *context);
+BLAKE3_API void blake3_hasher_init_derive_key_raw(blake3_hasher *self, const void *context,
+                                                  size_t context_len);
+BLAKE3_API void blake3_hasher_update(blake3_hasher *self, const void *input,
+                                     size_t input_len);
 #if defined(BLAKE3_USE_TBB)
-    BLAKE3_API void blake3_hasher_update_tbb(blake3_hasher *self, const void *input,
-                                             size_t input_len);
+BLAKE3_API void blake3_hasher_update_tbb(blake3_hasher *self, const void *input,
+                                         size_t input_len);
 #endif // BLAKE3_USE_TBB
-    BLAKE3_API void blake3_hasher_finalize(const blake3_hasher *self, uint8_t *out,
-                                           size_t out_len);
-    BLAKE3_API void blake3_hasher_finalize_seek(const blake3_hasher *self, uint64_t seek,
-                                                uint8_t *out, size_t out_len);
-    BLAKE3_API void blake3_hasher_reset(blake3_hasher *self);
+BLAKE3_API void blake3_hasher_finalize(const blake3_hasher *self, uint8_t *out,
+                                       size_t out_len);
+BLAKE3_API void blake3_hasher_finalize_seek(const blake3_hasher *self, uint64_t seek,
+                                            uint8_t *out, size_t out_len);
+BLAKE3_API void blake3_hasher_reset(blake3_hasher *self);
 
 #ifdef __cplusplus
 }
 #endif
 
-#endif /* BLAKE3_H */
\ No newline at end of file
+#endif /* BLAKE3_H */
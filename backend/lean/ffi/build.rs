@@ -1,7 +1,19 @@
 fn main() {
+    // Vendored BLAKE3 C sources, portable backend only: certificate inputs
+    // are a few KiB, so the SIMD kernels are not worth the assembler setup.
     cc::Build::new()
         .file("blake3.c")
+        .file("blake3_dispatch.c")
+        .file("blake3_portable.c")
         .file("ffi.c")
         .include(".")
+        .define("BLAKE3_NO_SSE2", None)
+        .define("BLAKE3_NO_SSE41", None)
+        .define("BLAKE3_NO_AVX2", None)
+        .define("BLAKE3_NO_AVX512", None)
+        .define("BLAKE3_USE_NEON", "0")
         .compile("sentinelffi");
+    for f in ["build.rs", "blake3.c", "blake3_dispatch.c", "blake3_portable.c", "ffi.c", "blake3.h", "blake3_impl.h"] {
+        println!("cargo:rerun-if-changed={f}");
+    }
 }
@@ -1,7 +1,74 @@
+//! Safe Rust bindings for the Sentinel C/Lean shim (`ffi.c`).
+//!
+//! `sentinel_cert_hash` hashes `prop_json ‖ trace_json` with BLAKE3.  Both
+//! inputs are complete JSON values, so the concatenation is unambiguous;
+//! producing them canonically is the caller's job (see proof-engine's
+//! `cert` module).
+
+use std::ffi::{c_char, CString};
+use std::fmt;
+
+extern "C" {
+    fn sentinel_cert_hash(prop_json: *const c_char, trace_json: *const c_char, out32: *mut u8) -> bool;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CertError {
+    /// An input contains a NUL byte and cannot cross the C boundary.
+    InteriorNul,
+    /// The shim reported failure.
+    Shim,
+}
+
+impl fmt::Display for CertError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CertError::InteriorNul => f.write_str("certificate input contains a NUL byte"),
+            CertError::Shim => f.write_str("sentinel_cert_hash failed"),
+        }
+    }
+}
+
+impl std::error::Error for CertError {}
+
+/// BLAKE3 certificate hash of a property and the trace it was judged on.
+pub fn cert_hash(prop_json: &str, trace_json: &str) -> Result<[u8; 32], CertError> {
+    let prop = CString::new(prop_json).map_err(|_| CertError::InteriorNul)?;
+    let trace = CString::new(trace_json).map_err(|_| CertError::InteriorNul)?;
+    let mut out = [0u8; 32];
+    // SAFETY: both pointers are valid NUL-terminated strings for the
+    // duration of the call and `out` has room for the 32 bytes written.
+    let ok = unsafe { sentinel_cert_hash(prop.as_ptr(), trace.as_ptr(), out.as_mut_ptr()) };
+    if ok { Ok(out) } else { Err(CertError::Shim) }
+}
+
+/// `cert_hash` as lowercase hex, the form carried in proof packets.
+pub fn cert_hash_hex(prop_json: &str, trace_json: &str) -> Result<String, CertError> {
+    use fmt::Write;
+    let mut hex = String::with_capacity(64);
+    for b in cert_hash(prop_json, trace_json)? {
+        let _ = write!(hex, "{b:02x}");
+    }
+    Ok(hex)
+}
+
+// ---------------------------
+// Unit tests
+// ---------------------------
 #[cfg(test)]
 mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_blake3_of_concatenation() {
+        let (prop, trace) = (r#"{"Le":["P",120.0]}"#, r#"[{"P":100.0}]"#);
+        let want = blake3::hash(format!("{prop}{trace}").as_bytes());
+        assert_eq!(cert_hash(prop, trace).unwrap(), *want.as_bytes());
+        assert_eq!(cert_hash_hex(prop, trace).unwrap(), want.to_hex().as_str());
+    }
+
     #[test]
-    fn it_works() {
-        assert_eq!(2 + 2, 4);
+    fn rejects_interior_nul() {
+        assert_eq!(cert_hash("a\0b", "[]"), Err(CertError::InteriorNul));
     }
 }
@@ -1,46 +1,32 @@
 //! Hourly Merkle-root builder and Polygon anchor.
 //! Run inside `batcher.rs` (see `ledger/bin/batcher.rs`).
+//! proof-engine publishes in transactions: consume `sentinel.proofs` with
+//! `isolation.level=read_committed` so aborted packets are never anchored.
 
-use blake3::Hasher;
+pub use crate::merkle::{build_merkle, packet_hash};
 use chrono::{DateTime, Timelike, Utc};
+use ethers::abi::{Function, Param, ParamType, Token};
 use ethers::prelude::*;
 use serde::Deserialize;
 use std::collections::VecDeque;
+use std::str::FromStr;
 use tokio_postgres::NoTls;
 
+/// Proof packet as published by proof-engine (fields the ledger reads).
 #[derive(Debug, Deserialize)]
-struct ProofPacket {
-    property_id: String,
-    start_ts: i64,
-    end_ts:   i64,
-    trace_hash: String,
-    cert_hash:  String,
-    verdict:    String,          // "PASS" | "FAIL"
-}
-
-/* ---------- hashing helpers ------------------------------------------------ */
-
-fn packet_hash(bytes: &[u8]) -> [u8; 32] { blake3::hash(bytes).into() }
-
-fn build_merkle(leaves: &[[u8; 32]]) -> ([u8; 32], Vec<[u8; 32]>) {
-    if leaves.is_empty() { return ([0; 32], vec![]); }
-    let mut level = leaves.to_vec();
-    let mut dag   = level.clone();
-    while level.len() > 1 {
-        let mut next = vec![];
-        for chunk in level.chunks(2) {
-            let cat = if chunk.len() == 2 {
-                [chunk[0].as_slice(), chunk[1].as_slice()].concat()
-            } else {
-                chunk[0].to_vec()
-            };
-            let h = blake3::hash(&cat).into();
-            next.push(h);
-        }
-        dag.extend(&next);
-        level = next;
-    }
-    (level[0], dag)
+pub struct ProofPacket {
+    pub property_id: String,
+    #[serde(default)]
+    pub asset: Option<String>,
+    pub start_ts: f64,
+    pub end_ts:   f64,
+    pub trace_hash: String,
+    pub cert_hash:  String,
+    pub verdict:    String,          // "PASS" | "FAIL"
+    #[serde(default)]
+    pub pack_version: Option<String>,
+    #[serde(default)]
+    pub verdict_source: Option<String>, // "rust" | "sat"
 }
 
 /* ---------- Polygon anchor ------------------------------------------------- */
@@ -65,11 +51,12 @@ async fn anchor_polygon(root: [u8; 32]) -> anyhow::Result<TxHash> {
             internal_type: None,
         }],
         outputs: vec![],
+        #[allow(deprecated)]
         constant: None,
         state_mutability: ethers::abi::StateMutability::NonPayable,
     };
     let data = f.encode_input(&[Token::FixedBytes(root.to_vec())])?;
-    let gas  = std::env::var("ANCHOR_GAS_LIMIT")
+    let gas: u64 = std::env::var("ANCHOR_GAS_LIMIT")
         .unwrap_or_else(|_| "80000".into()).parse()?;
 
     let pending = client
@@ -0,0 +1,120 @@
+//! Merkle tree over proof packets, as anchored by `batch::BatchAnchor`.
+//!
+//! Leaves are `BLAKE3(packet bytes)`.  Each level hashes pairs
+//! `BLAKE3(left ‖ right)`; an odd node at the end of a level is hashed on
+//! its own, `BLAKE3(node)`.  The stored `dag` is every level concatenated,
+//! leaves first and root last.
+
+pub type Hash = [u8; 32];
+
+pub fn packet_hash(bytes: &[u8]) -> Hash {
+    blake3::hash(bytes).into()
+}
+
+/// Root and full node list (`dag`) of the tree over `leaves`.
+pub fn build_merkle(leaves: &[Hash]) -> (Hash, Vec<Hash>) {
+    if leaves.is_empty() { return ([0; 32], vec![]); }
+    let mut level = leaves.to_vec();
+    let mut dag   = level.clone();
+    while level.len() > 1 {
+        let mut next = vec![];
+        for chunk in level.chunks(2) {
+            let cat = if chunk.len() == 2 {
+                [chunk[0].as_slice(), chunk[1].as_slice()].concat()
+            } else {
+                chunk[0].to_vec()
+            };
+            let h = blake3::hash(&cat).into();
+            next.push(h);
+        }
+        dag.extend(&next);
+        level = next;
+    }
+    (level[0], dag)
+}
+
+/// One step from a node towards the root.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Step {
+    /// Sibling sits to the left: parent = H(sibling ‖ node).
+    Left(Hash),
+    /// Sibling sits to the right: parent = H(node ‖ sibling).
+    Right(Hash),
+    /// Odd node out: parent = H(node).
+    Alone,
+}
+
+/// Split a stored `dag` back into its levels (leaves first).
+pub fn levels(dag: &[Hash]) -> Option<Vec<&[Hash]>> {
+    // Level sizes are fixed by the leaf count; find the one matching `dag`.
+    let sizes = |n: usize| {
+        let mut sizes = vec![n];
+        while *sizes.last().unwrap() > 1 {
+            sizes.push(sizes.last().unwrap().div_ceil(2));
+        }
+        sizes
+    };
+    let sizes = (1..=dag.len()).map(sizes).find(|s| s.iter().sum::<usize>() == dag.len())?;
+    let mut out = Vec::with_capacity(sizes.len());
+    let mut rest = dag;
+    for n in sizes {
+        let (level, tail) = rest.split_at(n);
+        out.push(level);
+        rest = tail;
+    }
+    Some(out)
+}
+
+/// Inclusion proof for leaf `index` of a stored `dag`.
+pub fn inclusion_proof(dag: &[Hash], index: usize) -> Option<Vec<Step>> {
+    let levels = levels(dag)?;
+    if index >= levels[0].len() {
+        return None;
+    }
+    let mut proof = Vec::new();
+    let mut i = index;
+    for level in &levels[..levels.len() - 1] {
+        proof.push(if i % 2 == 1 {
+            Step::Left(level[i - 1])
+        } else if i + 1 < level.len() {
+            Step::Right(level[i + 1])
+        } else {
+            Step::Alone
+        });
+        i /= 2;
+    }
+    Some(proof)
+}
+
+/// Whether `leaf` hashes up to `root` along `proof`.
+pub fn verify_inclusion(leaf: Hash, proof: &[Step], root: Hash) -> bool {
+    let node = proof.iter().fold(leaf, |node, step| match step {
+        Step::Left(sib) => blake3::hash(&[sib.as_slice(), node.as_slice()].concat()).into(),
+        Step::Right(sib) => blake3::hash(&[node.as_slice(), sib.as_slice()].concat()).into(),
+        Step::Alone => blake3::hash(&node).into(),
+    });
+    node == root
+}
+
+// ---------------------------
+// Unit tests
+// ---------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_leaf_proves_into_the_root() {
+        for n in 1..=9 {
+            let leaves: Vec<Hash> = (0..n).map(|i| packet_hash(format!("packet {i}").as_bytes())).collect();
+            let (root, dag) = build_merkle(&leaves);
+            assert_eq!(levels(&dag).unwrap()[0], leaves.as_slice());
+            for (i, leaf) in leaves.iter().enumerate() {
+                let proof = inclusion_proof(&dag, i).unwrap();
+                assert!(verify_inclusion(*leaf, &proof, root), "n={n} i={i}");
+                assert!(!verify_inclusion(packet_hash(b"forged"), &proof, root));
+            }
+            assert!(inclusion_proof(&dag, n).is_none());
+        }
+    }
+}
@@ -1,18 +1,45 @@
 // benches/engine_bench.rs  (multi-pack benchmarks)
-use criterion::{criterion_group, criterion_main, Criterion};
-use proof_engine::{dsl, monitor::PropertyMonitor};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use proof_engine::cnf_tseitin::TseitinEncoder;
+use proof_engine::sat::{SatCore, SolveMode};
+use proof_engine::dsl;
+use proof_engine::monitor::solver_context;
+use proof_engine::MonitorSet;
 use std::collections::HashMap;
 
 type Trace = Vec<HashMap<dsl::Var, f64>>;
 
+/// A pack of `n` distinct properties in the shapes a real pack mixes:
+/// plain bounds, conjunctions sharing the MAOP atom, and windowed rates.
+fn pack(n: usize) -> Vec<(dsl::Prop, usize)> {
+    use dsl::{Prop, Var};
+    let maop = || Box::new(Prop::Le(Var::P, 120.0));
+    (0..n)
+        .map(|i| {
+            let k = i as f64;
+            let prop = match i % 3 {
+                0 => Prop::Le(Var::P, 100.0 + k),
+                1 => Prop::And(maop(), Box::new(Prop::Le(Var::T, 40.0 + k))),
+                _ => Prop::WindowAll(3, Box::new(Prop::RateBound(Var::P, 5.0 + k))),
+            };
+            (prop, 6)
+        })
+        .collect()
+}
+
 fn bench_pack(c: &mut Criterion, n: usize) {
     let bench_name = format!("engine_latency_{}", n);
-    let props: Vec<dsl::Prop> = (0..n).map(|_| dsl::Prop::Le(dsl::Var::P, 120.0)).collect();
-    let mut eng = PropertyMonitor::new(props[0].clone(), 6);
-    let mut sample = HashMap::new();
-    sample.insert(dsl::Var::P, 100.0);
-    let window: Trace = vec![sample; 6];
-    c.bench_function(&bench_name, |b| b.iter(|| eng.tick(&window)));
+    let ctx = solver_context();
+    let mut eng = MonitorSet::new(&ctx, pack(n)).unwrap();
+    assert_eq!(eng.len(), n);
+    let mut t = 0u32;
+    c.bench_function(&bench_name, |b| {
+        b.iter(|| {
+            t = t.wrapping_add(1);
+            let p = 100.0 + f64::from(t % 7);
+            eng.tick(i64::from(t) * 1_000_000_000, HashMap::from([(dsl::Var::P, p), (dsl::Var::T, 40.0)]))
+        })
+    });
 }
 
 fn benches(c: &mut Criterion) {
@@ -21,5 +48,48 @@ fn benches(c: &mut Criterion) {
     }
 }
 
+/// Naive vs incremental `SatCore` on a sliding window of 6 ticks, each tick
+/// the Tseitin encoding of a 16-conjunct pressure/temperature pack.
+fn sat_window(c: &mut Criterion) {
+    let conjuncts: Vec<dsl::Prop> = (0..16)
+        .map(|i| {
+            let v = if i % 2 == 0 { dsl::Var::P } else { dsl::Var::T };
+            dsl::Prop::Le(v, 100.0 + i as f64)
+        })
+        .collect();
+    let prop = conjuncts
+        .into_iter()
+        .reduce(|a, b| dsl::Prop::And(Box::new(a), Box::new(b)))
+        .unwrap();
+    let windows: Vec<Trace> = (0..12)
+        .map(|t| {
+            // every fourth tick violates the first conjunct
+            let p = if t % 4 == 3 { 130.0 } else { 90.0 };
+            vec![HashMap::from([(dsl::Var::P, p), (dsl::Var::T, 40.0)])]
+        })
+        .collect();
+    let per_tick = TseitinEncoder::new().encode(&prop, &windows[0]).len();
+
+    let mut cfg = z3::Config::new();
+    cfg.set_timeout_msec(100);
+    let ctx = z3::Context::new(&cfg);
+
+    let mut group = c.benchmark_group("sat_window");
+    for mode in [SolveMode::Naive, SolveMode::Incremental] {
+        let mut enc = TseitinEncoder::new();
+        let mut sat = SatCore::with_mode(&ctx, 6 * per_tick, mode);
+        let mut t = 0;
+        group.bench_function(BenchmarkId::from_parameter(format!("{mode:?}")), |b| {
+            b.iter(|| {
+                let delta = enc.encode(&prop, &windows[t % windows.len()]);
+                t += 1;
+                sat.unsat_recycle(delta).expect("solver")
+            })
+        });
+    }
+    group.finish();
+}
+
 criterion_group!(engine_latency, benches);
-criterion_main!(engine_latency);
+criterion_group!(sat_modes, sat_window);
+criterion_main!(engine_latency, sat_modes);
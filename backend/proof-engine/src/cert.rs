@@ -0,0 +1,126 @@
+//! Canonical certificate inputs and their hash.
+//!
+//! A certificate binds a property to the window it was judged on.  Its hash
+//! is `BLAKE3(prop_json ‖ trace_json)`, computed by the `sentinel_cert_hash`
+//! shim in `libsentinel_ffi`, over two canonical JSON documents:
+//!
+//! * `prop_json`  – `{"format":"sentinel-cert/2","prop":<Prop>}` with the
+//!   `Prop` in serde's externally tagged form and tags by name, e.g.
+//!   `{"format":"sentinel-cert/2","prop":{"Le":["P",120.0]}}`;
+//! * `trace_json` – the window newest-first, one object per sample with its
+//!   timestamp in ns and its tags sorted by name, e.g.
+//!   `[{"ts_ns":2000,"tags":{"P":101.5,"T":20.0}},{"ts_ns":1000,"tags":{"P":99.0}}]`.
+//!   Timestamps are part of the certificate because `|dX|` bounds are
+//!   judged per second of sample time.
+//!
+//! Numbers use serde_json's shortest round-trip form; non-finite readings
+//! become `null`.  The same property and readings therefore hash the same on
+//! every run and machine, independent of tag ids or map iteration order.
+
+use crate::dsl::{Prop, Sample};
+use sentinel_trace_hash::TraceSample;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+pub use libsentinel_ffi::CertError;
+
+/// Version tag embedded in `prop_json`; bump on any format change.
+pub const CERT_FORMAT: &str = "sentinel-cert/2";
+
+#[derive(Serialize)]
+struct CertProp<'a> {
+    format: &'static str,
+    prop: &'a Prop,
+}
+
+pub fn prop_json(p: &Prop) -> String {
+    serde_json::to_string(&CertProp { format: CERT_FORMAT, prop: p }).expect("Prop serialises")
+}
+
+#[derive(Serialize)]
+struct CertSample {
+    ts_ns: i64,
+    tags: BTreeMap<String, f64>,
+}
+
+/// `window` newest-first with its timestamps in ns, parallel to it.
+pub fn trace_json(window: &[Sample], stamps_ns: &[i64]) -> String {
+    debug_assert_eq!(window.len(), stamps_ns.len());
+    let samples: Vec<CertSample> = window
+        .iter()
+        .zip(stamps_ns)
+        .map(|(s, &ts_ns)| CertSample { ts_ns, tags: s.iter().map(|(v, x)| (v.name().to_string(), *x)).collect() })
+        .collect();
+    serde_json::to_string(&samples).expect("trace serialises")
+}
+
+/// Canonical `trace_hash` (see `sentinel_trace_hash`) of a newest-first
+/// window and its timestamps in ns.
+pub fn trace_hash(window: &[Sample], stamps_ns: &[i64]) -> String {
+    debug_assert_eq!(window.len(), stamps_ns.len());
+    let samples: Vec<TraceSample> = window
+        .iter()
+        .zip(stamps_ns)
+        .rev()
+        .map(|(s, &ts)| TraceSample::new(ts, s.iter().map(|(v, x)| (v.name().to_string(), *x))))
+        .collect();
+    sentinel_trace_hash::hash_hex(&samples)
+}
+
+/// Hex certificate hash of `p` judged on `window` (newest-first) taken at
+/// `stamps_ns`.
+pub fn cert_hash(p: &Prop, window: &[Sample], stamps_ns: &[i64]) -> Result<String, CertError> {
+    libsentinel_ffi::cert_hash_hex(&prop_json(p), &trace_json(window, stamps_ns))
+}
+
+// ---------------------------
+// Unit tests
+// ---------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dsl::Var;
+    use std::collections::HashMap;
+
+    #[test]
+    fn canonical_json() {
+        let p = Prop::And(Box::new(Prop::Le(Var::P, 120.0)), Box::new(Prop::Eq(Var::Valve, 1.0, 0.0)));
+        assert_eq!(
+            prop_json(&p),
+            r#"{"format":"sentinel-cert/2","prop":{"And":[{"Le":["P",120.0]},{"Eq":["Valve",1.0,0.0]}]}}"#
+        );
+        let window = vec![
+            HashMap::from([(Var::T, 20.0), (Var::P, 101.5), (Var::Flow, f64::NAN)]),
+            HashMap::from([(Var::P, 99.0)]),
+        ];
+        assert_eq!(
+            trace_json(&window, &[2_000, 1_000]),
+            r#"[{"ts_ns":2000,"tags":{"Flow":null,"P":101.5,"T":20.0}},{"ts_ns":1000,"tags":{"P":99.0}}]"#
+        );
+    }
+
+    #[test]
+    fn hash_is_reproducible() {
+        let p = Prop::Le(Var::P, 120.0);
+        let a = vec![HashMap::from([(Var::P, 100.0), (Var::T, 20.0)])];
+        // same readings, different insertion order
+        let b = vec![HashMap::from([(Var::T, 20.0), (Var::P, 100.0)])];
+        let h = cert_hash(&p, &a, &[0]).unwrap();
+        assert_eq!(h, cert_hash(&p, &b, &[0]).unwrap());
+        let want = blake3::hash(format!("{}{}", prop_json(&p), trace_json(&a, &[0])).as_bytes());
+        assert_eq!(h, want.to_hex().as_str());
+        assert_ne!(h, cert_hash(&Prop::Le(Var::P, 121.0), &a, &[0]).unwrap());
+        // same readings taken at another time
+        assert_ne!(h, cert_hash(&p, &a, &[1]).unwrap());
+    }
+
+    #[test]
+    fn trace_hash_is_oldest_first_and_by_name() {
+        let window = vec![HashMap::from([(Var::P, 101.5)]), HashMap::from([(Var::T, 20.0), (Var::P, 100.0)])];
+        let want = sentinel_trace_hash::hash_hex(&[
+            TraceSample::new(1_000, [("P", 100.0), ("T", 20.0)]),
+            TraceSample::new(2_000, [("P", 101.5)]),
+        ]);
+        assert_eq!(trace_hash(&window, &[2_000, 1_000]), want);
+    }
+}
@@ -0,0 +1,120 @@
+// proof-engine/src/checkpoint.rs
+// =============================================================
+// Station snapshots for restart recovery.
+// -------------------------------------------------------------
+// * A snapshot is one asset's window, the samples its reorder buffer still
+//   holds, and by property id its last published verdict, raised margin
+//   alarm and monitor history (the own verdicts of the ticks its held
+//   verdict is taken over).
+// * Encoded as a header line `sentinel-checkpoint <format> <blake3>` and
+//   the JSON body the checksum covers.  A snapshot of another format or
+//   with a bad checksum is rejected whole, never half-restored.
+// * Solver state is not serialized: `Stations::restore` refills the
+//   window and rebuilds each monitor from its history (`Engine::resume`).
+//   Replaying the window would not do: a tick's verdict can depend on
+//   samples that have already left it.
+// =============================================================
+
+use crate::dsl::{Sample, Truth};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use thiserror::Error;
+
+/// Snapshot layout version; bump on any change to `Snapshot`.
+pub const FORMAT: u32 = 3;
+
+const MAGIC: &str = "sentinel-checkpoint";
+
+#[derive(Error, Debug)]
+pub enum CheckpointError {
+    #[error("not a checkpoint: bad header")]
+    Header,
+    #[error("checkpoint format {0} is not supported (this build reads {FORMAT})")]
+    Format(u32),
+    #[error("checkpoint checksum mismatch")]
+    Checksum,
+    #[error("malformed checkpoint: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub asset: String,
+    /// Pack version the verdicts were reached under.
+    pub pack_version: String,
+    /// Window samples, oldest first.
+    pub window: Vec<(i64, Sample)>,
+    /// Samples held back by the reorder buffer, oldest first.
+    pub pending: Vec<(i64, Sample)>,
+    pub newest: Option<i64>,
+    pub released: Option<i64>,
+    /// Last published verdict by property id.
+    pub verdicts: BTreeMap<String, Truth>,
+    /// Properties whose last packet raised a margin alarm.
+    pub alarms: BTreeSet<String>,
+    /// `PropertyMonitor::history` by property id.
+    pub history: BTreeMap<String, Vec<Truth>>,
+}
+
+impl Snapshot {
+    pub fn encode(&self) -> Vec<u8> {
+        let body = serde_json::to_vec(self).expect("snapshot is plain data");
+        let mut out = format!("{MAGIC} {FORMAT} {}\n", blake3::hash(&body).to_hex()).into_bytes();
+        out.extend(body);
+        out
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<Self, CheckpointError> {
+        let nl = bytes.iter().position(|&b| b == b'\n').ok_or(CheckpointError::Header)?;
+        let header = std::str::from_utf8(&bytes[..nl]).map_err(|_| CheckpointError::Header)?;
+        let body = &bytes[nl + 1..];
+        let mut fields = header.split(' ');
+        let (Some(MAGIC), Some(format), Some(checksum), None) =
+            (fields.next(), fields.next(), fields.next(), fields.next())
+        else {
+            return Err(CheckpointError::Header);
+        };
+        let format: u32 = format.parse().map_err(|_| CheckpointError::Header)?;
+        if format != FORMAT {
+            return Err(CheckpointError::Format(format));
+        }
+        if blake3::hash(body).to_hex().as_str() != checksum {
+            return Err(CheckpointError::Checksum);
+        }
+        Ok(serde_json::from_slice(body)?)
+    }
+}
+
+// ---------------------------
+// Unit tests
+// ---------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dsl::Var;
+    use std::collections::HashMap;
+
+    #[test]
+    fn rejects_foreign_and_corrupt_snapshots() {
+        let snap = Snapshot {
+            asset: "seg7".into(),
+            pack_version: "t-1".into(),
+            window: vec![(1, HashMap::from([(Var::P, 101.5)]))],
+            pending: vec![],
+            newest: Some(1),
+            released: Some(1),
+            verdicts: BTreeMap::from([("max_pressure".into(), Truth::False)]),
+            alarms: BTreeSet::from(["ramp".into()]),
+            history: BTreeMap::from([("max_pressure".into(), vec![Truth::True, Truth::False])]),
+        };
+        let bytes = snap.encode();
+        assert_eq!(Snapshot::decode(&bytes).unwrap(), snap);
+
+        let mut flipped = bytes.clone();
+        *flipped.last_mut().unwrap() ^= 1;
+        assert!(matches!(Snapshot::decode(&flipped), Err(CheckpointError::Checksum)));
+        let future = String::from_utf8(bytes).unwrap().replacen(&format!(" {FORMAT} "), &format!(" {} ", FORMAT + 1), 1);
+        assert!(matches!(Snapshot::decode(future.as_bytes()), Err(CheckpointError::Format(f)) if f == FORMAT + 1));
+        assert!(matches!(Snapshot::decode(b"{}"), Err(CheckpointError::Header)));
+    }
+}
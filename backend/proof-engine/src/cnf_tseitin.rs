@@ -1 +1,421 @@
-// Boolean Tseitin encoder
\ No newline at end of file
+// proof-engine/src/cnf_tseitin.rs
+// =============================================================
+// Tseitin CNF encoder for Sentinel proof‑engine.
+// -------------------------------------------------------------
+// Replaces the empty‑clause shortcut of `cnf.rs` with a structural
+// translation of `dsl::Prop` into `sat::Clause`s:
+//   • Every leaf (`Le`, `Ge`, `Eq`, …, `RateBound`) becomes a **threshold atom** – a fresh
+//     variable pinned by a unit clause to its truth value on the sampled
+//     window.  The solver never sees raw readings, only which atoms hold.
+//   • Every `And` / `Or` node gets a fresh auxiliary variable `g` together
+//     with the usual three Tseitin clauses defining `g ↔ (a ∘ b)`;
+//     `Implies` is the `Or` gate over `¬a, b` and `Not` just flips a literal.
+//   • Temporal nodes are unrolled over the window: `WindowAll` becomes an
+//     n‑ary AND of its child on each dropped suffix, `ImplWithin` an n‑ary
+//     OR of `¬p` and `q` on the suffixes `drop 0 ..= drop k`.
+//   • The root variable is asserted as a unit clause.
+//   • Missing readings use a dual rail: an `Unknown` atom gets two
+//     variables, "proved" and "refuted", both pinned false, and gates above
+//     it carry both rails (Kleene `∧`/`∨`, `¬` swaps them).  Subtrees without
+//     unknown atoms keep the single-variable encoding (refuted = ¬proved).
+//     The root assertion is "not refuted"; `Encoding::proved` tells whether
+//     the window also proves the property.
+//
+// The resulting clause set is SAT iff `eval_truth(p, τ)` is not `False`
+// (on complete samples: iff `eval_prop(p, τ)`), but unlike `⊥` a violation
+// is now *explained* by the atom clauses that conflict with the root –
+// exactly what the UNSAT core needs.
+//
+// Variable ids are allocated by a `TseitinEncoder` so that encodings of
+// successive ticks never alias inside one solver window.  Each clause is
+// tagged with a `ClauseOrigin` (tick + pre‑order node index) so UNSAT core
+// indices can be mapped back to the sub‑formula and reading that broke it.
+// =============================================================
+
+use crate::dsl::{drop_newest, eval_truth, Missing, Prop, Sample, Truth};
+use crate::sat::{Clause, Lit};
+
+/// Truth value of a threshold atom: `(pre-order node, atom, window suffix,
+/// its timestamps)`.
+pub type Leaf<'a> = &'a mut dyn FnMut(usize, &Prop, &[Sample], &[i64]) -> Truth;
+
+/// Stateful encoder handing out fresh variable ids across ticks.
+#[derive(Debug, Default)]
+pub struct TseitinEncoder {
+    next_var: i32,
+}
+
+/// Provenance of one clause: the tick that produced it and the pre-order
+/// index of the `Prop` sub-node it defines (see `Prop::node`).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ClauseOrigin {
+    pub tick: u64,
+    pub node: usize,
+}
+
+/// Clauses of one tick with their origins (parallel vectors).
+#[derive(Debug, Default)]
+pub struct Encoding {
+    pub clauses: Vec<Clause>,
+    pub origins: Vec<ClauseOrigin>,
+    /// Literal that is true in every model iff the window proves the
+    /// property; `None` when nothing is left to prove.
+    pub proved: Option<Lit>,
+}
+
+/// Both rails of a node: literals for "proved" and "refuted".
+#[derive(Clone, Copy)]
+struct Rail {
+    t: Lit,
+    f: Lit,
+}
+
+impl Rail {
+    fn crisp(t: Lit) -> Self {
+        Rail { t, f: neg(t) }
+    }
+
+    fn is_crisp(&self) -> bool {
+        self.f == neg(self.t)
+    }
+}
+
+impl Encoding {
+    /// Attribute every clause of an untracked encoder (e.g. `cnf::delta_clauses`)
+    /// to the root node.
+    pub fn untracked(clauses: Vec<Clause>, tick: u64) -> Self {
+        let origins = vec![ClauseOrigin { tick, node: 0 }; clauses.len()];
+        Self { clauses, origins, proved: None }
+    }
+
+    /// Append `clause`, attributed to pre-order node `node` of `tick`.
+    pub fn push(&mut self, clause: Clause, tick: u64, node: usize) {
+        self.clauses.push(clause);
+        self.origins.push(ClauseOrigin { tick, node });
+    }
+}
+
+impl TseitinEncoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Next variable id to be allocated.
+    pub fn num_vars(&self) -> i32 {
+        self.next_var
+    }
+
+    /// Ids wrap around at `i32::MAX`; by then every clause that used the
+    /// low ids has long aged out of any solver window.
+    fn fresh(&mut self) -> Lit {
+        let var = self.next_var;
+        self.next_var = self.next_var.wrapping_add(1) & i32::MAX;
+        Lit { var, neg: false }
+    }
+
+    /// A root that the window neither proves nor refutes, for properties
+    /// judged outside the encoding (see `PropertyMonitor`).
+    pub fn undecided(&mut self, tick: u64) -> Encoding {
+        let t = self.fresh();
+        let mut out = Encoding { proved: Some(t), ..Encoding::default() };
+        out.push(Clause(vec![neg(t)]), tick, 0);
+        out
+    }
+
+    /// Encode `p` on `window` (newest-first) and assert its root.
+    ///
+    /// The returned clauses are satisfiable iff `eval_truth(p, window)` is
+    /// not `False`.
+    pub fn encode(&mut self, p: &Prop, window: &[Sample]) -> Vec<Clause> {
+        self.encode_traced(p, window, 0).clauses
+    }
+
+    /// Like `encode`, but records the origin of every clause for `tick`.
+    pub fn encode_traced(&mut self, p: &Prop, window: &[Sample], tick: u64) -> Encoding {
+        self.encode_traced_with(p, window, &[], tick, &mut |_, atom, w, ts| eval_truth(atom, w, ts, Missing::Unknown))
+    }
+
+    /// Like `encode_traced`, on a timestamped window (`stamps` in ns,
+    /// parallel to `window`, or empty) and with threshold atoms decided by
+    /// `leaf(node, atom, suffix, stamps)` – e.g. a cache shared between
+    /// properties.  `leaf` must agree with `eval_truth(atom, suffix, stamps, _)`
+    /// under the property's missing-data policy.
+    pub fn encode_traced_with(
+        &mut self,
+        p: &Prop,
+        window: &[Sample],
+        stamps: &[i64],
+        tick: u64,
+        leaf: Leaf<'_>,
+    ) -> Encoding {
+        let mut out = Encoding::default();
+        let root = self.encode_node(p, (window, stamps), tick, 0, leaf, &mut out);
+        out.push(Clause(vec![neg(root.f)]), tick, 0);
+        out.proved = Some(root.t);
+        out
+    }
+
+    /// Emit the defining clauses of `p` (pre-order index `node`) into `out`,
+    /// returning the rails that stand for `p`.
+    fn encode_node(
+        &mut self,
+        p: &Prop,
+        (window, stamps): (&[Sample], &[i64]),
+        tick: u64,
+        node: usize,
+        leaf: Leaf<'_>,
+        out: &mut Encoding,
+    ) -> Rail {
+        use Prop::*;
+        let suffix = |n: usize| (drop_newest(window, n), drop_newest(stamps, n));
+        match p {
+            Le(..) | Ge(..) | Lt(..) | Gt(..) | Eq(..) | Ne(..) | RateBound(..) => {
+                // Threshold atom: truth value is fixed by the sampled window.
+                let a = self.fresh();
+                match leaf(node, p, window, stamps) {
+                    Truth::Unknown => {
+                        // Neither proved nor refuted.
+                        let f = self.fresh();
+                        out.push(Clause(vec![neg(a)]), tick, node);
+                        out.push(Clause(vec![neg(f)]), tick, node);
+                        Rail { t: a, f }
+                    }
+                    holds => {
+                        out.push(Clause(vec![if holds == Truth::True { a } else { neg(a) }]), tick, node);
+                        Rail::crisp(a)
+                    }
+                }
+            }
+            WindowAll(k, q) => {
+                // Unrolled over the suffixes `eval_prop` visits.
+                let rails: Vec<Rail> = (0..=*k)
+                    .take_while(|i| *i == *k || *i < window.len())
+                    .map(|i| self.encode_node(q, suffix(i), tick, node + 1, leaf, out))
+                    .collect();
+                self.gate(&rails, true, tick, node, out)
+            }
+            ImplWithin(pre, post, k) => {
+                let a = self.encode_node(pre, (window, stamps), tick, node + 1, leaf, out);
+                let post_node = node + 1 + pre.size();
+                let mut rails = vec![not(a)];
+                for n in 0..=*k {
+                    rails.push(self.encode_node(post, suffix(n), tick, post_node, leaf, out));
+                }
+                self.gate(&rails, false, tick, node, out)
+            }
+            And(l, r) => {
+                let a = self.encode_node(l, (window, stamps), tick, node + 1, leaf, out);
+                let b = self.encode_node(r, (window, stamps), tick, node + 1 + l.size(), leaf, out);
+                self.gate(&[a, b], true, tick, node, out)
+            }
+            Or(l, r) => {
+                let a = self.encode_node(l, (window, stamps), tick, node + 1, leaf, out);
+                let b = self.encode_node(r, (window, stamps), tick, node + 1 + l.size(), leaf, out);
+                self.gate(&[a, b], false, tick, node, out)
+            }
+            // ¬ needs no gate: the child's rails, swapped.
+            Not(q) => not(self.encode_node(q, (window, stamps), tick, node + 1, leaf, out)),
+            Implies(l, r) => {
+                let a = self.encode_node(l, (window, stamps), tick, node + 1, leaf, out);
+                let b = self.encode_node(r, (window, stamps), tick, node + 1 + l.size(), leaf, out);
+                self.gate(&[not(a), b], false, tick, node, out)
+            }
+        }
+    }
+
+    /// Kleene `⋀ rails` (`conj`) or `⋁ rails`: one gate on the proved rails,
+    /// and the dual gate on the refuted rails unless every input is crisp.
+    fn gate(&mut self, rails: &[Rail], conj: bool, tick: u64, node: usize, out: &mut Encoding) -> Rail {
+        let ts: Vec<Lit> = rails.iter().map(|r| r.t).collect();
+        let t = if conj { self.and_gate(&ts, tick, node, out) } else { self.or_gate(&ts, tick, node, out) };
+        if rails.iter().all(Rail::is_crisp) {
+            return Rail::crisp(t);
+        }
+        let fs: Vec<Lit> = rails.iter().map(|r| r.f).collect();
+        let f = if conj { self.or_gate(&fs, tick, node, out) } else { self.and_gate(&fs, tick, node, out) };
+        Rail { t, f }
+    }
+
+    /// Fresh `g ↔ ⋀ lits` (an empty conjunction is `true`).
+    fn and_gate(&mut self, lits: &[Lit], tick: u64, node: usize, out: &mut Encoding) -> Lit {
+        let g = self.fresh();
+        // g → lᵢ  for every i,  (⋀ lᵢ) → g
+        for &l in lits {
+            out.push(Clause(vec![neg(g), l]), tick, node);
+        }
+        let mut back = vec![g];
+        back.extend(lits.iter().map(|&l| neg(l)));
+        out.push(Clause(back), tick, node);
+        g
+    }
+
+    /// Fresh `g ↔ ⋁ lits`.
+    fn or_gate(&mut self, lits: &[Lit], tick: u64, node: usize, out: &mut Encoding) -> Lit {
+        let g = self.fresh();
+        // g → ⋁ lᵢ,  lᵢ → g  for every i
+        let mut fwd = vec![neg(g)];
+        fwd.extend_from_slice(lits);
+        out.push(Clause(fwd), tick, node);
+        for &l in lits {
+            out.push(Clause(vec![g, neg(l)]), tick, node);
+        }
+        g
+    }
+}
+
+#[inline]
+fn neg(l: Lit) -> Lit {
+    Lit { var: l.var, neg: !l.neg }
+}
+
+#[inline]
+fn not(r: Rail) -> Rail {
+    Rail { t: r.f, f: r.t }
+}
+
+/// Generate the **delta** clause set for the new tick using the Tseitin
+/// translation.  Drop-in replacement for `cnf::delta_clauses`.
+pub fn delta_clauses_tseitin(enc: &mut TseitinEncoder, p: &Prop, window: &[Sample]) -> Vec<Clause> {
+    enc.encode(p, window)
+}
+
+// ---------------------------
+// Unit tests
+// ---------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dsl::{eval_prop, eval_truth, Missing, Truth, Var};
+    use std::collections::HashMap;
+
+    fn sample(p: f64) -> Sample {
+        HashMap::from([(Var::P, p)])
+    }
+
+    /// Brute-force satisfiability check (tiny formulas only).
+    fn satisfiable(clauses: &[Clause]) -> bool {
+        let n = clauses.iter().flat_map(|c| c.0.iter()).map(|l| l.var + 1).max().unwrap_or(0);
+        assert!(n <= 20, "formula too large for brute force");
+        (0u32..(1 << n)).any(|asg| {
+            clauses.iter().all(|c| c.0.iter().any(|l| ((asg >> l.var) & 1 == 1) != l.neg))
+        })
+    }
+
+    fn check(p: &Prop, trace: &[Sample]) {
+        let clauses = TseitinEncoder::new().encode(p, trace);
+        assert_eq!(satisfiable(&clauses), eval_prop(p, trace), "{p:?} on {trace:?}");
+    }
+
+    #[test]
+    fn atom_matches_eval() {
+        check(&Prop::Le(Var::P, 10.0), &[sample(5.0)]);
+        check(&Prop::Le(Var::P, 1.0), &[sample(5.0)]);
+        check(&Prop::RateBound(Var::P, 2.0), &[sample(9.0), sample(5.0)]);
+        check(&Prop::RateBound(Var::P, 2.0), &[]);
+    }
+
+    #[test]
+    fn gates_match_eval() {
+        let ok = Prop::Le(Var::P, 10.0);
+        let bad = Prop::RateBound(Var::P, 1.0);
+        let trace = vec![sample(8.0), sample(2.0)];
+        for p in [
+            Prop::And(Box::new(ok.clone()), Box::new(bad.clone())),
+            Prop::Or(Box::new(bad.clone()), Box::new(ok.clone())),
+            Prop::And(Box::new(ok.clone()), Box::new(Prop::Or(Box::new(bad.clone()), Box::new(bad)))),
+        ] {
+            check(&p, &trace);
+        }
+    }
+
+    #[test]
+    fn comparisons_and_connectives_match_eval() {
+        let le = |k| Box::new(Prop::Le(Var::P, k));
+        let props = [
+            Prop::Ge(Var::P, 5.0),
+            Prop::Lt(Var::P, 5.0),
+            Prop::Gt(Var::P, 5.0),
+            Prop::Eq(Var::P, 5.0, 0.5),
+            Prop::Ne(Var::P, 5.0, 0.5),
+            Prop::Not(le(5.0)),
+            Prop::Implies(Box::new(Prop::Gt(Var::P, 1.0)), le(5.0)),
+            Prop::Not(Box::new(Prop::And(le(5.0), Box::new(Prop::Not(le(2.0)))))),
+        ];
+        for p in &props {
+            for t in [vec![], vec![sample(5.0)], vec![sample(1.0)], vec![sample(8.0)]] {
+                check(p, &t);
+            }
+        }
+    }
+
+    #[test]
+    fn temporal_match_eval() {
+        let le = |k| Box::new(Prop::Le(Var::P, k));
+        let props = [
+            Prop::WindowAll(0, le(5.0)),
+            Prop::WindowAll(2, le(5.0)),
+            Prop::WindowAll(4, Box::new(Prop::RateBound(Var::P, 3.0))),
+            Prop::ImplWithin(le(0.0), le(3.0), 1),
+            Prop::ImplWithin(le(0.0), le(-1.0), 2),
+        ];
+        let traces: [&[f64]; 5] = [&[], &[0.0], &[1.0, 9.0], &[0.0, 5.0, 2.0], &[0.0, 4.0, 4.5, -2.0]];
+        for p in &props {
+            for t in traces {
+                let t: Vec<Sample> = t.iter().map(|&v| sample(v)).collect();
+                check(p, &t);
+            }
+        }
+    }
+
+    #[test]
+    fn fresh_vars_across_ticks() {
+        let mut enc = TseitinEncoder::new();
+        let p = Prop::Le(Var::P, 10.0);
+        let first = enc.encode(&p, &[sample(5.0)]);
+        let second = enc.encode(&p, &[sample(50.0)]);
+        let max_first = first.iter().flat_map(|c| c.0.iter()).map(|l| l.var).max().unwrap();
+        assert!(second.iter().flat_map(|c| c.0.iter()).all(|l| l.var > max_first));
+        // Each tick is self-contained: the violating tick alone is UNSAT.
+        assert!(satisfiable(&first));
+        assert!(!satisfiable(&second));
+    }
+
+    #[test]
+    fn origins_follow_preorder() {
+        // node 0 = And, 1 = Le, 2 = RateBound
+        let p = Prop::And(Box::new(Prop::Le(Var::P, 10.0)), Box::new(Prop::RateBound(Var::P, 1.0)));
+        let enc = TseitinEncoder::new().encode_traced(&p, &[sample(8.0), sample(2.0)], 7);
+        assert_eq!(enc.clauses.len(), enc.origins.len());
+        assert!(enc.origins.iter().all(|o| o.tick == 7));
+        let nodes: Vec<usize> = enc.origins.iter().map(|o| o.node).collect();
+        assert_eq!(nodes, vec![1, 2, 0, 0, 0, 0]);
+        assert_eq!(p.node(2), Some(&Prop::RateBound(Var::P, 1.0)));
+    }
+
+    #[test]
+    fn unknown_atoms_are_neither_proved_nor_refuted() {
+        let partial = |p: Option<f64>| {
+            let mut s = HashMap::from([(Var::T, 20.0)]);
+            s.extend(p.map(|p| (Var::P, p)));
+            s
+        };
+        let le = |v, k| Box::new(Prop::Le(v, k));
+        let props = [
+            Prop::Le(Var::P, 10.0),
+            Prop::Not(le(Var::P, 10.0)),
+            Prop::Or(le(Var::P, 10.0), le(Var::T, 50.0)),
+            Prop::And(le(Var::P, 10.0), le(Var::T, 5.0)),
+            Prop::Implies(le(Var::T, 50.0), le(Var::P, 10.0)),
+            Prop::WindowAll(1, le(Var::P, 10.0)),
+        ];
+        for p in &props {
+            for t in [vec![partial(None), partial(Some(5.0))], vec![partial(Some(50.0)), partial(None)]] {
+                let want = eval_truth(p, &t, &[], Missing::Unknown);
+                let mut enc = TseitinEncoder::new().encode_traced(p, &t, 0);
+                assert_eq!(satisfiable(&enc.clauses), want != Truth::False, "{p:?} on {t:?}");
+                enc.clauses.push(Clause(vec![enc.proved.unwrap()]));
+                assert_eq!(satisfiable(&enc.clauses), want == Truth::True, "{p:?} on {t:?}");
+            }
+        }
+    }
+}
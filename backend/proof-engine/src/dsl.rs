@@ -1,36 +1,605 @@
 //! Minimal Rust mirror of the Lean DSL, plus a tiny executable `eval_prop`.
 //! Only what the proof-engine needs right now.
 
-use serde::Serialize;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::str::FromStr;
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize)]
-pub enum Var { P, T, Flow, Valve }
+/// Interned PLC tag id; names live in the `tags` registry.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Var(pub(crate) u32);
+
+#[allow(non_upper_case_globals)]
+impl Var {
+    pub const P: Var = Var(0);
+    pub const T: Var = Var(1);
+    pub const Flow: Var = Var(2);
+    pub const Valve: Var = Var(3);
+
+    /// Id of tag `name`, registering it while the registry is open; `None`
+    /// for a name a closed registry does not list (see
+    /// `tags::lookup_or_intern`).
+    pub fn named(name: &str) -> Option<Var> {
+        crate::tags::lookup_or_intern(name)
+    }
+
+    pub fn name(&self) -> std::sync::Arc<str> {
+        crate::tags::name_of(*self)
+    }
+
+    pub fn id(&self) -> u32 {
+        self.0
+    }
+}
+
+impl fmt::Debug for Var {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.name())
+    }
+}
+
+impl fmt::Display for Var {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.name())
+    }
+}
+
+/// Tags serialise by name so ids never leak into certificates.
+impl Serialize for Var {
+    fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        s.serialize_str(&self.name())
+    }
+}
+
+impl<'de> Deserialize<'de> for Var {
+    fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(d)?;
+        Var::named(&name).ok_or_else(|| serde::de::Error::custom(format!("unknown tag {name:?}")))
+    }
+}
 
 #[derive(Clone, Debug, PartialEq, Serialize)]
 pub enum Prop {
     Le(Var, f64),
+    Ge(Var, f64),
+    Lt(Var, f64),
+    Gt(Var, f64),
+    /// `Eq(v, k, tol)`: `|v - k| <= tol` (use `tol = 0` for discrete tags).
+    Eq(Var, f64, f64),
+    /// `Ne(v, k, tol)`: `|v - k| > tol`.
+    Ne(Var, f64, f64),
+    /// `RateBound(v, k)`: `v` changes by at most `k` per second between
+    /// the two newest samples.  Without timestamps (as in the Lean `eval`)
+    /// consecutive samples count as one second apart.  The Lean `rateBound`
+    /// is this untimed bound, so `eval_sound` covers published verdicts on
+    /// it only when the samples are 1 s apart.
     RateBound(Var, f64),
+    /// `windowAll k p`: `p` holds on the current window and on each of the
+    /// `k` windows obtained by dropping the newest sample.
+    WindowAll(usize, Box<Prop>),
+    /// `implWithin p q k`: if `p` holds now, `q` holds on one of the windows
+    /// dropping `0..=k` newest samples.
+    ImplWithin(Box<Prop>, Box<Prop>, usize),
     And(Box<Prop>, Box<Prop>),
     Or(Box<Prop>, Box<Prop>),
-    // temporal nodes omitted for brevity
+    Not(Box<Prop>),
+    Implies(Box<Prop>, Box<Prop>),
+}
+
+impl Prop {
+    /// Number of nodes in the syntax tree.
+    pub fn size(&self) -> usize {
+        use Prop::*;
+        match self {
+            Le(..) | Ge(..) | Lt(..) | Gt(..) | Eq(..) | Ne(..) | RateBound(..) => 1,
+            WindowAll(_, p) | Not(p) => 1 + p.size(),
+            ImplWithin(a, b, _) | And(a, b) | Or(a, b) | Implies(a, b) => 1 + a.size() + b.size(),
+        }
+    }
+
+    /// Sub-node at pre-order index `idx` (0 = `self`).
+    pub fn node(&self, idx: usize) -> Option<&Prop> {
+        use Prop::*;
+        if idx == 0 {
+            return Some(self);
+        }
+        match self {
+            Le(..) | Ge(..) | Lt(..) | Gt(..) | Eq(..) | Ne(..) | RateBound(..) => None,
+            WindowAll(_, p) | Not(p) => p.node(idx - 1),
+            ImplWithin(a, b, _) | And(a, b) | Or(a, b) | Implies(a, b) => {
+                let n = a.size();
+                if idx <= n { a.node(idx - 1) } else { b.node(idx - 1 - n) }
+            }
+        }
+    }
+
+    /// Samples of history the property looks at: 1 for current-sample
+    /// atoms, 2 for `RateBound`, plus the drops of the temporal operators.
+    pub fn history(&self) -> usize {
+        use Prop::*;
+        match self {
+            Le(..) | Ge(..) | Lt(..) | Gt(..) | Eq(..) | Ne(..) => 1,
+            RateBound(..) => 2,
+            WindowAll(k, p) => k + p.history(),
+            ImplWithin(a, b, k) => a.history().max(k + b.history()),
+            Not(p) => p.history(),
+            And(a, b) | Or(a, b) | Implies(a, b) => a.history().max(b.history()),
+        }
+    }
 }
 
 /// Evaluate the Boolean DSL on a trace window (newest-first).
 pub type Sample = std::collections::HashMap<Var, f64>;
 pub type Trace  = Vec<Sample>;
 
-pub fn eval_prop(p: &Prop, trace: &Trace) -> bool {
+/// `List.drop n` on a newest-first window (or its timestamps).
+#[inline]
+pub fn drop_newest<T>(trace: &[T], n: usize) -> &[T] {
+    &trace[n.min(trace.len())..]
+}
+
+/// Seconds between the two newest samples, 1 when they carry no timestamps.
+#[inline]
+fn step_secs(stamps: &[i64]) -> f64 {
+    match stamps {
+        [newest, prev, ..] => newest.saturating_sub(*prev) as f64 / 1e9,
+        _ => 1.0,
+    }
+}
+
+/// Three-valued (Kleene) truth of a property on a window that may lack
+/// readings.  Ordered so that `And` is `min` and `Or` is `max`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Truth {
+    False,
+    /// Decided by a reading the window does not have.
+    Unknown,
+    True,
+}
+
+impl Truth {
+    /// Verdict as published in proof packets.
+    pub fn verdict(self) -> &'static str {
+        match self {
+            Truth::True => "PASS",
+            Truth::False => "FAIL",
+            Truth::Unknown => "INCONCLUSIVE",
+        }
+    }
+}
+
+impl From<bool> for Truth {
+    fn from(b: bool) -> Self {
+        if b { Truth::True } else { Truth::False }
+    }
+}
+
+impl std::ops::Not for Truth {
+    type Output = Truth;
+
+    fn not(self) -> Truth {
+        match self {
+            Truth::True => Truth::False,
+            Truth::False => Truth::True,
+            Truth::Unknown => Truth::Unknown,
+        }
+    }
+}
+
+/// What a property makes of a tag absent from a sample.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Missing {
+    /// Read the latest earlier value of the tag inside the window; still
+    /// unknown if the window has none.
+    HoldLast,
+    /// A verdict that hinges on a missing reading is a violation.
+    Violation,
+    /// A verdict that hinges on a missing reading is inconclusive.
+    #[default]
+    Unknown,
+}
+
+impl Missing {
+    /// The property's verdict from its `eval_truth`.
+    pub fn resolve(self, t: Truth) -> Truth {
+        match (self, t) {
+            (Missing::Violation, Truth::Unknown) => Truth::False,
+            _ => t,
+        }
+    }
+}
+
+impl FromStr for Missing {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "hold_last" => Ok(Missing::HoldLast),
+            "violation" => Ok(Missing::Violation),
+            "unknown" => Ok(Missing::Unknown),
+            other => Err(format!("unknown missing-data policy {other:?} (expected `hold_last`, `violation` or `unknown`)")),
+        }
+    }
+}
+
+impl fmt::Display for Missing {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Missing::HoldLast => "hold_last",
+            Missing::Violation => "violation",
+            Missing::Unknown => "unknown",
+        })
+    }
+}
+
+/// Reading of `v` on the newest sample – or with `HoldLast` the newest
+/// sample that has one – `None` when absent.
+#[inline]
+fn reading(trace: &[Sample], v: &Var, missing: Missing) -> Option<f64> {
+    match missing {
+        Missing::HoldLast => trace.iter().find_map(|s| s.get(v)).copied(),
+        _ => trace.first()?.get(v).copied(),
+    }
+}
+
+/// Current-sample atom: `f(value)` on the newest sample, `true` on `[]`.
+#[inline]
+fn now(trace: &[Sample], v: &Var, f: impl FnOnce(f64) -> bool) -> bool {
+    trace.first()
+        .map(|s| f(s.get(v).copied().unwrap_or(0.0)))
+        .unwrap_or(true)
+}
+
+/// Mirror of the Lean `eval`, which reads an absent tag as 0 (`findD v 0`).
+/// The Lean proof (`eval_sound`) covers this two-valued evaluator only, so
+/// it backs a published verdict (`eval_truth`) only on a complete window –
+/// every sample carrying every tag `p` reads – where the two agree, and,
+/// if `p` has `RateBound` atoms, only when the samples are 1 s apart.
+/// INCONCLUSIVE verdicts and the missing-data policies are outside it.
+pub fn eval_prop(p: &Prop, trace: &[Sample]) -> bool {
+    eval_timed(p, trace, &[])
+}
+
+/// `eval_prop` with the sample timestamps (ns, parallel to `trace`), so
+/// `RateBound` is judged per second of real time.  An empty `stamps`
+/// gives the untimed semantics.
+pub fn eval_timed(p: &Prop, trace: &[Sample], stamps: &[i64]) -> bool {
     use Prop::*;
+    let at = |q: &Prop, n: usize| eval_timed(q, drop_newest(trace, n), drop_newest(stamps, n));
     match p {
-        Le(v, k) => trace.first()
-            .map(|s| s.get(v).copied().unwrap_or(0.0) <= *k)
-            .unwrap_or(true),
+        Le(v, k) => now(trace, v, |x| x <= *k),
+        Ge(v, k) => now(trace, v, |x| x >= *k),
+        Lt(v, k) => now(trace, v, |x| x < *k),
+        Gt(v, k) => now(trace, v, |x| x > *k),
+        Eq(v, k, tol) => now(trace, v, |x| (x - *k).abs() <= *tol),
+        Ne(v, k, tol) => now(trace, v, |x| (x - *k).abs() > *tol),
         RateBound(v, k) => trace.get(1)
             .and_then(|prev| trace.first().map(|cur| {
-                (cur.get(v).unwrap_or(&0.0) - prev.get(v).unwrap_or(&0.0)).abs() <= *k
+                (cur.get(v).unwrap_or(&0.0) - prev.get(v).unwrap_or(&0.0)).abs() <= *k * step_secs(stamps)
             }))
             .unwrap_or(true),
-        And(a, b) => eval_prop(a, trace) && eval_prop(b, trace),
-        Or(a, b)  => eval_prop(a, trace) || eval_prop(b, trace),
+        // windowAll 0 p τ = eval p τ ; windowAll (k+1) p [] = true ;
+        // windowAll (k+1) p τ@(_ :: rest) = eval p τ && eval (windowAll k p) rest
+        WindowAll(k, q) => (0..=*k)
+            .take_while(|i| *i == *k || *i < trace.len())
+            .all(|i| at(q, i)),
+        ImplWithin(a, b, k) => !at(a, 0)
+            || (0..=*k).any(|n| at(b, n)),
+        And(a, b) => at(a, 0) && at(b, 0),
+        Or(a, b)  => at(a, 0) || at(b, 0),
+        Not(a) => !at(a, 0),
+        Implies(a, b) => !at(a, 0) || at(b, 0),
+    }
+}
+
+/// Kleene evaluation of `p` on a timestamped window: atoms on a missing
+/// reading are `Unknown`, and `And`/`Or`/`Not` propagate it only where it
+/// decides the outcome.  `missing` picks how readings are looked up
+/// (`HoldLast`); apply `Missing::resolve` for the property's verdict.  On
+/// windows carrying every tag `p` reads this agrees with `eval_timed`; the
+/// Kleene semantics itself has no Lean model (see `eval_prop`).
+pub fn eval_truth(p: &Prop, trace: &[Sample], stamps: &[i64], missing: Missing) -> Truth {
+    use Prop::*;
+    let at = |q: &Prop, n: usize| eval_truth(q, drop_newest(trace, n), drop_newest(stamps, n), missing);
+    let now = |v: &Var, f: &dyn Fn(f64) -> bool| match trace {
+        [] => Truth::True,
+        _ => reading(trace, v, missing).map_or(Truth::Unknown, |x| f(x).into()),
+    };
+    match p {
+        Le(v, k) => now(v, &|x| x <= *k),
+        Ge(v, k) => now(v, &|x| x >= *k),
+        Lt(v, k) => now(v, &|x| x < *k),
+        Gt(v, k) => now(v, &|x| x > *k),
+        Eq(v, k, tol) => now(v, &|x| (x - *k).abs() <= *tol),
+        Ne(v, k, tol) => now(v, &|x| (x - *k).abs() > *tol),
+        RateBound(..) if trace.len() < 2 => Truth::True,
+        RateBound(v, k) => match (reading(trace, v, missing), reading(&trace[1..], v, missing)) {
+            (Some(cur), Some(prev)) => ((cur - prev).abs() <= *k * step_secs(stamps)).into(),
+            _ => Truth::Unknown,
+        },
+        WindowAll(k, q) => (0..=*k)
+            .take_while(|i| *i == *k || *i < trace.len())
+            .map(|i| at(q, i))
+            .min()
+            .unwrap_or(Truth::True),
+        ImplWithin(a, b, k) => (0..=*k).map(|n| at(b, n)).fold(!at(a, 0), Truth::max),
+        And(a, b) => at(a, 0).min(at(b, 0)),
+        Or(a, b) => at(a, 0).max(at(b, 0)),
+        Not(a) => !at(a, 0),
+        Implies(a, b) => (!at(a, 0)).max(at(b, 0)),
+    }
+}
+
+/// Current-sample margin: `f(value)` on the newest sample, `+inf` on `[]`
+/// and `NaN` on a missing reading.
+#[inline]
+fn margin_now(trace: &[Sample], v: &Var, missing: Missing, f: impl FnOnce(f64) -> f64) -> f64 {
+    match trace {
+        [] => f64::INFINITY,
+        _ => reading(trace, v, missing).map_or(f64::NAN, f),
+    }
+}
+
+/// `min` where `NaN` (unknown) survives unless the other side is negative.
+fn and_margin(a: f64, b: f64) -> f64 {
+    if a < 0.0 || b < 0.0 || !(a.is_nan() || b.is_nan()) { a.min(b) } else { f64::NAN }
+}
+
+/// `max` where `NaN` (unknown) survives unless the other side is positive.
+fn or_margin(a: f64, b: f64) -> f64 {
+    if a > 0.0 || b > 0.0 || !(a.is_nan() || b.is_nan()) { a.max(b) } else { f64::NAN }
+}
+
+/// STL-style robustness of `p` on a trace window (newest-first): how far
+/// the readings are from flipping the verdict, in the units of the tags
+/// involved.  Positive means `eval_prop` holds, negative means it fails;
+/// `+inf` marks a vacuous truth (e.g. an atom on `[]`).
+///
+/// Atoms give their distance to the threshold (`k - x` for `Le`,
+/// `k·Δt - |Δx|` for `RateBound`, `tol - |x - k|` for `Eq`, ...), `And`
+/// and the temporal `WindowAll` take the minimum, `Or` and `ImplWithin`
+/// the maximum, and `Not` negates.  Missing readings make the margin
+/// `NaN` wherever `eval_truth` is `Unknown`.
+pub fn robustness(p: &Prop, trace: &[Sample]) -> f64 {
+    robustness_timed(p, trace, &[], Missing::Unknown)
+}
+
+/// `robustness` with sample timestamps, as in `eval_timed`, reading tags
+/// as `eval_truth` does under `missing`.
+pub fn robustness_timed(p: &Prop, trace: &[Sample], stamps: &[i64], missing: Missing) -> f64 {
+    use Prop::*;
+    let at = |q: &Prop, n: usize| robustness_timed(q, drop_newest(trace, n), drop_newest(stamps, n), missing);
+    match p {
+        Le(v, k) | Lt(v, k) => margin_now(trace, v, missing, |x| *k - x),
+        Ge(v, k) | Gt(v, k) => margin_now(trace, v, missing, |x| x - *k),
+        Eq(v, k, tol) => margin_now(trace, v, missing, |x| *tol - (x - *k).abs()),
+        Ne(v, k, tol) => margin_now(trace, v, missing, |x| (x - *k).abs() - *tol),
+        RateBound(..) if trace.len() < 2 => f64::INFINITY,
+        RateBound(v, k) => match (reading(trace, v, missing), reading(&trace[1..], v, missing)) {
+            (Some(cur), Some(prev)) => *k * step_secs(stamps) - (cur - prev).abs(),
+            _ => f64::NAN,
+        },
+        WindowAll(k, q) => (0..=*k)
+            .take_while(|i| *i == *k || *i < trace.len())
+            .map(|i| at(q, i))
+            .fold(f64::INFINITY, and_margin),
+        ImplWithin(a, b, k) => (0..=*k)
+            .map(|n| at(b, n))
+            .fold(-at(a, 0), or_margin),
+        And(a, b) => and_margin(at(a, 0), at(b, 0)),
+        Or(a, b) => or_margin(at(a, 0), at(b, 0)),
+        Not(a) => -at(a, 0),
+        Implies(a, b) => or_margin(-at(a, 0), at(b, 0)),
+    }
+}
+
+// ---------------------------
+// Unit tests – expected values follow `eval` in lean/PropSound.lean
+// ---------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn trace(ps: &[f64]) -> Trace {
+        ps.iter().map(|&p| HashMap::from([(Var::P, p)])).collect()
+    }
+
+    fn le(k: f64) -> Box<Prop> {
+        Box::new(Prop::Le(Var::P, k))
+    }
+
+    #[test]
+    fn window_all_matches_lean() {
+        // windowAll 0 p τ = eval p τ
+        assert!(!eval_prop(&Prop::WindowAll(0, le(5.0)), &trace(&[9.0, 1.0])));
+        assert!(eval_prop(&Prop::WindowAll(0, le(5.0)), &trace(&[1.0, 9.0])));
+        // windowAll k p checks the k+1 newest suffixes
+        assert!(!eval_prop(&Prop::WindowAll(1, le(5.0)), &trace(&[1.0, 9.0])));
+        assert!(eval_prop(&Prop::WindowAll(1, le(5.0)), &trace(&[1.0, 2.0, 9.0])));
+        assert!(!eval_prop(&Prop::WindowAll(2, le(5.0)), &trace(&[1.0, 2.0, 9.0])));
+        // windowAll _ p [] = true
+        assert!(eval_prop(&Prop::WindowAll(3, le(-1.0)), &[]));
+        // short trace: recursion stops at [] with `true`
+        assert!(eval_prop(&Prop::WindowAll(5, le(5.0)), &trace(&[1.0, 2.0])));
+    }
+
+    #[test]
+    fn impl_within_matches_lean() {
+        // valve closed (P <= 0 here) ⇒ flow drops within k ticks
+        let p = Prop::ImplWithin(le(0.0), le(3.0), 1);
+        // antecedent false ⇒ true
+        assert!(eval_prop(&p, &trace(&[9.0, 9.0])));
+        // antecedent true, q holds at drop 1
+        assert!(eval_prop(&p, &trace(&[0.0, 2.0])));
+        // antecedent true, q holds at drop 0 (0 <= 3)
+        assert!(eval_prop(&Prop::ImplWithin(le(0.0), le(0.0), 0), &trace(&[0.0])));
+        // antecedent true, q fails on drops 0..=1
+        let q_strict = Prop::ImplWithin(le(0.0), le(-1.0), 1);
+        assert!(!eval_prop(&q_strict, &trace(&[0.0, 5.0, -2.0])));
+        // drop beyond the end yields [] where `le` is vacuously true
+        assert!(eval_prop(&Prop::ImplWithin(le(0.0), le(-1.0), 2), &trace(&[0.0, 5.0])));
+    }
+
+    fn at(p: f64) -> Trace {
+        trace(&[p])
+    }
+
+    #[test]
+    fn ge_matches_lean() {
+        // ge v k (s :: _) = decide (k ≤ s.findD v 0) ; ge _ _ [] = true
+        assert!(eval_prop(&Prop::Ge(Var::P, 10.0), &at(10.0)));
+        assert!(!eval_prop(&Prop::Ge(Var::P, 10.0), &at(9.5)));
+        assert!(eval_prop(&Prop::Ge(Var::P, 10.0), &[]));
+    }
+
+    #[test]
+    fn lt_matches_lean() {
+        // lt v k (s :: _) = decide (s.findD v 0 < k) ; lt _ _ [] = true
+        assert!(eval_prop(&Prop::Lt(Var::P, 10.0), &at(9.5)));
+        assert!(!eval_prop(&Prop::Lt(Var::P, 10.0), &at(10.0)));
+        assert!(eval_prop(&Prop::Lt(Var::P, 10.0), &[]));
+    }
+
+    #[test]
+    fn gt_matches_lean() {
+        // gt v k (s :: _) = decide (k < s.findD v 0) ; gt _ _ [] = true
+        assert!(eval_prop(&Prop::Gt(Var::P, 10.0), &at(10.5)));
+        assert!(!eval_prop(&Prop::Gt(Var::P, 10.0), &at(10.0)));
+        assert!(eval_prop(&Prop::Gt(Var::P, 10.0), &[]));
+    }
+
+    #[test]
+    fn eq_matches_lean() {
+        // eq v k tol (s :: _) = decide (|s.findD v 0 - k| ≤ tol) ; eq _ _ _ [] = true
+        let valve_open = Prop::Eq(Var::Valve, 1.0, 0.0);
+        let s = |v| vec![HashMap::from([(Var::Valve, v)])];
+        assert!(eval_prop(&valve_open, &s(1.0)));
+        assert!(!eval_prop(&valve_open, &s(0.0)));
+        assert!(eval_prop(&Prop::Eq(Var::P, 5.0, 0.5), &at(5.5)));
+        assert!(!eval_prop(&Prop::Eq(Var::P, 5.0, 0.5), &at(5.6)));
+        // absent tag reads as 0 (findD v 0)
+        assert!(eval_prop(&Prop::Eq(Var::T, 0.0, 0.0), &at(3.0)));
+        assert!(eval_prop(&valve_open, &[]));
+    }
+
+    #[test]
+    fn ne_matches_lean() {
+        // ne v k tol (s :: _) = decide (tol < |s.findD v 0 - k|) ; ne _ _ _ [] = true
+        assert!(eval_prop(&Prop::Ne(Var::P, 5.0, 0.5), &at(5.6)));
+        assert!(!eval_prop(&Prop::Ne(Var::P, 5.0, 0.5), &at(5.5)));
+        assert!(!eval_prop(&Prop::Ne(Var::P, 5.0, 0.0), &at(5.0)));
+        assert!(eval_prop(&Prop::Ne(Var::P, 5.0, 0.0), &[]));
+    }
+
+    #[test]
+    fn not_matches_lean() {
+        // not p τ = !eval p τ – in particular `not` of a vacuous atom is false on []
+        assert!(eval_prop(&Prop::Not(le(5.0)), &at(6.0)));
+        assert!(!eval_prop(&Prop::Not(le(5.0)), &at(4.0)));
+        assert!(!eval_prop(&Prop::Not(le(5.0)), &[]));
+    }
+
+    #[test]
+    fn implies_matches_lean() {
+        // implies p q τ = !eval p τ || eval q τ
+        let p = Prop::Implies(Box::new(Prop::Ge(Var::P, 10.0)), le(20.0));
+        assert!(eval_prop(&p, &at(5.0)));
+        assert!(eval_prop(&p, &at(15.0)));
+        assert!(!eval_prop(&p, &at(25.0)));
+        assert!(eval_prop(&p, &[]));
+    }
+
+    #[test]
+    fn node_indexing_is_preorder() {
+        let p = Prop::And(
+            Box::new(Prop::WindowAll(2, le(1.0))),
+            Box::new(Prop::ImplWithin(le(2.0), le(3.0), 1)),
+        );
+        assert_eq!(p.size(), 6);
+        assert_eq!(p.node(2), Some(&Prop::Le(Var::P, 1.0)));
+        assert_eq!(p.node(4), Some(&Prop::Le(Var::P, 2.0)));
+        assert_eq!(p.node(5), Some(&Prop::Le(Var::P, 3.0)));
+        assert_eq!(p.node(6), None);
+    }
+
+    #[test]
+    fn robustness_is_a_signed_margin() {
+        let near = Prop::Le(Var::P, 120.0);
+        assert!((robustness(&near, &at(119.9)) - 0.1).abs() < 1e-9);
+        assert_eq!(robustness(&near, &at(20.0)), 100.0);
+        assert_eq!(robustness(&near, &at(125.0)), -5.0);
+        assert_eq!(robustness(&near, &[]), f64::INFINITY);
+        // |Δ| = 3 against 5 on the newest pair
+        assert_eq!(robustness(&Prop::RateBound(Var::P, 5.0), &trace(&[10.0, 7.0, 100.0])), 2.0);
+        // min over the window, max over the alternatives
+        let p = Prop::Or(Box::new(Prop::WindowAll(2, le(10.0))), Box::new(Prop::Ge(Var::P, 8.0)));
+        assert_eq!(robustness(&p, &trace(&[2.0, 4.0, 3.0, 50.0])), 6.0);
+        assert_eq!(robustness(&Prop::Not(le(10.0)), &at(4.0)), -6.0);
+    }
+
+    #[test]
+    fn robustness_sign_agrees_with_eval() {
+        let props = [
+            Prop::WindowAll(2, Box::new(Prop::And(le(5.0), Box::new(Prop::RateBound(Var::P, 2.0))))),
+            Prop::ImplWithin(Box::new(Prop::Ge(Var::P, 4.0)), le(1.0), 1),
+            Prop::Implies(Box::new(Prop::Ne(Var::P, 3.0, 0.5)), Box::new(Prop::Eq(Var::P, 6.0, 1.0))),
+        ];
+        let traces = [vec![1.0, 2.0, 3.0], vec![4.5, 0.5, 9.0], vec![6.0, 7.0], vec![5.5, 3.0, 1.2, 0.0]];
+        for p in &props {
+            for t in &traces {
+                let r = robustness(p, &trace(t));
+                assert!(r != 0.0, "{p:?} {t:?} on the boundary");
+                assert_eq!(r > 0.0, eval_prop(p, &trace(t)), "{p:?} on {t:?}: {r}");
+            }
+        }
+    }
+
+    #[test]
+    fn rate_bound_is_per_second_with_timestamps() {
+        const SEC: i64 = 1_000_000_000;
+        let ramp = Prop::RateBound(Var::P, 2.0);
+        // +6 over 5 s is 1.2/s; the same step 1 s apart is 6/s
+        assert!(eval_timed(&ramp, &trace(&[16.0, 10.0]), &[10 * SEC, 5 * SEC]));
+        assert!(!eval_timed(&ramp, &trace(&[16.0, 10.0]), &[6 * SEC, 5 * SEC]));
+        // untimed: one second per sample
+        assert!(!eval_prop(&ramp, &trace(&[16.0, 10.0])));
+        assert_eq!(robustness_timed(&ramp, &trace(&[16.0, 10.0]), &[10 * SEC, 5 * SEC], Missing::Unknown), 4.0);
+        let p = Prop::WindowAll(1, Box::new(ramp));
+        assert!(!eval_timed(&p, &trace(&[11.0, 10.0, 4.0]), &[3 * SEC, 2 * SEC, SEC / 2]));
+        assert!(eval_timed(&p, &trace(&[11.0, 10.0, 4.0]), &[5 * SEC, 4 * SEC, SEC]));
+    }
+
+    #[test]
+    fn missing_readings_are_unknown_not_zero() {
+        use Truth::*;
+        let dropout = vec![HashMap::from([(Var::T, 20.0)]), HashMap::from([(Var::P, 130.0), (Var::T, 20.0)])];
+        let low_p = Prop::Le(Var::P, 120.0);
+        let t_ok = Prop::Le(Var::T, 80.0);
+        // `eval_prop` (the Lean mirror) reads the dropout as P = 0
+        assert!(eval_prop(&low_p, &dropout));
+        assert_eq!(eval_truth(&low_p, &dropout, &[], Missing::Unknown), Unknown);
+        assert!(robustness(&low_p, &dropout).is_nan());
+        // unknown only where it decides the outcome
+        let or = Prop::Or(Box::new(low_p.clone()), Box::new(t_ok.clone()));
+        let and = Prop::And(Box::new(low_p.clone()), Box::new(Prop::Not(Box::new(t_ok))));
+        assert_eq!(eval_truth(&or, &dropout, &[], Missing::Unknown), True);
+        assert_eq!(eval_truth(&and, &dropout, &[], Missing::Unknown), False);
+        assert_eq!(robustness(&or, &dropout), 60.0);
+        // policies
+        assert_eq!(eval_truth(&low_p, &dropout, &[], Missing::HoldLast), False);
+        assert_eq!(Missing::Violation.resolve(eval_truth(&low_p, &dropout, &[], Missing::Violation)), False);
+        assert_eq!(Missing::Unknown.resolve(Unknown).verdict(), "INCONCLUSIVE");
+        assert_eq!(eval_truth(&Prop::RateBound(Var::P, 5.0), &dropout, &[], Missing::Unknown), Unknown);
+        assert_eq!("hold_last".parse(), Ok(Missing::HoldLast));
+        // complete windows: same as the two-valued evaluator
+        let props = [Prop::WindowAll(2, le(5.0)), Prop::ImplWithin(le(0.0), le(3.0), 1), Prop::Not(le(2.0))];
+        for p in &props {
+            for t in [trace(&[1.0, 9.0]), trace(&[0.0, 2.0, 7.0]), vec![]] {
+                assert_eq!(eval_truth(p, &t, &[], Missing::Unknown), eval_prop(p, &t).into(), "{p:?} on {t:?}");
+            }
+        }
     }
 }
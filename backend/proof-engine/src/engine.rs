@@ -0,0 +1,232 @@
+//! Verdict engine used by the proof-engine binary: a `MonitorSet` driven
+//! either by the SAT monitors or by the pure-Rust evaluator.
+//!
+//! Both modes flip PASS/FAIL on the same tick: `sat` keeps a violation
+//! UNSAT until its tick ages out of the property's horizon, and `rust` holds
+//! the worst verdict over those same ticks.  Only `sat` reports an UNSAT
+//! core and solver time.
+
+use crate::dsl::{Sample, Truth};
+use crate::monitor::{MonitorError, Monitored};
+use crate::monitor_set::MonitorSet;
+use serde::Serialize;
+use std::fmt;
+use std::str::FromStr;
+use z3::Context;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EngineMode {
+    /// `dsl::eval_truth` on the window.
+    Rust,
+    /// `PropertyMonitor` / `SatCore`.
+    #[default]
+    Sat,
+}
+
+impl FromStr for EngineMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "rust" => Ok(EngineMode::Rust),
+            "sat" => Ok(EngineMode::Sat),
+            other => Err(format!("unknown engine mode {other:?} (expected `rust` or `sat`)")),
+        }
+    }
+}
+
+impl fmt::Display for EngineMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            EngineMode::Rust => "rust",
+            EngineMode::Sat => "sat",
+        })
+    }
+}
+
+/// One property's verdict for a tick.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Verdict {
+    /// PASS, FAIL or INCONCLUSIVE (`Truth::verdict`).
+    pub truth: Truth,
+    pub source: EngineMode,
+    /// UNSAT core clause indices (`sat` only, empty when the property holds).
+    pub core: Vec<usize>,
+    /// Solver wall time in µs (`sat` only).
+    pub solver_us: Option<u64>,
+}
+
+pub struct Engine<'ctx> {
+    ctx: &'ctx Context,
+    mode: EngineMode,
+    set: MonitorSet<'ctx>,
+}
+
+impl<'ctx> Engine<'ctx> {
+    pub fn new(
+        ctx: &'ctx Context,
+        mode: EngineMode,
+        props: impl IntoIterator<Item = impl Into<Monitored>>,
+    ) -> Result<Self, MonitorError> {
+        Ok(Engine { ctx, mode, set: MonitorSet::new(ctx, props)? })
+    }
+
+    pub fn mode(&self) -> EngineMode {
+        self.mode
+    }
+
+    pub fn monitors(&self) -> &MonitorSet<'ctx> {
+        &self.set
+    }
+
+    /// Current window, newest-first.
+    pub fn window(&self) -> &[Sample] {
+        self.set.window()
+    }
+
+    /// Timestamps (ns) of `window`, newest-first.
+    pub fn timestamps(&self) -> &[i64] {
+        self.set.timestamps()
+    }
+
+    /// Swap the property list, keeping the window and unchanged monitors.
+    pub fn reload(&mut self, props: impl IntoIterator<Item = impl Into<Monitored>>) -> Result<(), MonitorError> {
+        self.set.reload(self.ctx, props)
+    }
+
+    /// Per property, the verdicts its held verdict is taken over
+    /// (`MonitorSet::histories`), for `resume`.
+    pub fn histories(&self) -> Vec<Vec<Truth>> {
+        self.set.histories()
+    }
+
+    /// Take up a saved window (oldest first) and per-property histories on
+    /// a fresh engine, so the next tick reports what it would have without
+    /// the restart.
+    pub fn resume(&mut self, window: impl IntoIterator<Item = (i64, Sample)>, histories: &[Option<Vec<Truth>>]) {
+        self.set.resume(window, histories, self.mode == EngineMode::Sat);
+    }
+
+    /// Rust-evaluator verdicts, one per property, had the newest tick seen
+    /// the newest-first `window` (`MonitorSet::rejudge`); earlier ticks are
+    /// held as in `tick`.
+    pub fn judge(&self, window: &[Sample], stamps: &[i64]) -> Vec<Verdict> {
+        self.set
+            .rejudge(window, stamps)
+            .into_iter()
+            .map(|truth| Verdict { truth, source: EngineMode::Rust, core: Vec::new(), solver_us: None })
+            .collect()
+    }
+
+    /// Slot a late sample into the window and re-judge the newest tick with
+    /// it, in the engine's own mode.  The verdicts replace the ones that
+    /// tick reported, and later ticks build on them.
+    pub fn insert_late(&mut self, ts_ns: i64, sample: Sample) -> Vec<Verdict> {
+        self.set.insert(ts_ns, sample);
+        let truths = match self.mode {
+            EngineMode::Rust => self.set.reeval(),
+            EngineMode::Sat => self.set.retick(),
+        };
+        self.verdicts(truths)
+    }
+
+    pub fn tick(&mut self, ts_ns: i64, sample: Sample) -> Vec<Verdict> {
+        let truths = match self.mode {
+            EngineMode::Rust => self.set.eval(ts_ns, sample),
+            EngineMode::Sat => self.set.tick(ts_ns, sample),
+        };
+        self.verdicts(truths)
+    }
+
+    fn verdicts(&self, truths: Vec<Truth>) -> Vec<Verdict> {
+        truths
+            .into_iter()
+            .enumerate()
+            .map(|(i, truth)| match self.mode {
+                EngineMode::Rust => Verdict { truth, source: EngineMode::Rust, core: Vec::new(), solver_us: None },
+                EngineMode::Sat => Verdict {
+                    truth,
+                    source: EngineMode::Sat,
+                    core: self.set.monitor(i).last_core.clone(),
+                    solver_us: Some(self.set.solve_us(i)),
+                },
+            })
+            .collect()
+    }
+}
+
+// ---------------------------
+// Unit tests
+// ---------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dsl::{Prop, Var};
+    use crate::monitor::solver_context;
+    use std::collections::HashMap;
+
+    #[test]
+    fn modes_report_their_source() {
+        assert_eq!("SAT".parse::<EngineMode>(), Ok(EngineMode::Sat));
+        assert!("z3".parse::<EngineMode>().is_err());
+
+        let ctx = solver_context();
+        let props = || vec![(Prop::Le(Var::P, 120.0), 3)];
+        let high = HashMap::from([(Var::P, 130.0)]);
+        let mut sat = Engine::new(&ctx, EngineMode::Sat, props()).unwrap();
+        let v = sat.tick(0, high.clone()).remove(0);
+        assert!(v.truth == Truth::False && !v.core.is_empty() && v.solver_us.is_some());
+        assert_eq!(v.source, EngineMode::Sat);
+
+        let mut rust = Engine::new(&ctx, EngineMode::Rust, props()).unwrap();
+        let v = rust.tick(0, high).remove(0);
+        assert_eq!(v, Verdict { truth: Truth::False, source: EngineMode::Rust, core: vec![], solver_us: None });
+    }
+
+    #[test]
+    fn modes_flip_on_the_same_tick() {
+        let ctx = solver_context();
+        let props = || {
+            ["P <= 120", "always[2](P <= 120)", "P <= 120 && T <= 80"]
+                .map(|s| (crate::parser::parse_prop(s).unwrap(), 3))
+        };
+        let mut sat = Engine::new(&ctx, EngineMode::Sat, props()).unwrap();
+        let mut rust = Engine::new(&ctx, EngineMode::Rust, props()).unwrap();
+        let trace = [Some(100.0), Some(130.0), Some(100.0), None, Some(100.0), Some(100.0), Some(100.0), Some(100.0)];
+        let mut flips = Vec::new();
+        for (ts, p) in trace.into_iter().enumerate() {
+            let sample: HashMap<Var, f64> = p.map(|p| (Var::P, p)).into_iter().chain([(Var::T, 20.0)]).collect();
+            let truths = |v: Vec<Verdict>| v.into_iter().map(|v| v.truth).collect::<Vec<_>>();
+            let (s, r) = (truths(sat.tick(ts as i64, sample.clone())), truths(rust.tick(ts as i64, sample)));
+            assert_eq!(s, r, "tick {ts}");
+            flips.push(r[0]);
+        }
+        // the FAIL at tick 1 holds for the horizon, then the gap at tick 3 does
+        use Truth::*;
+        assert_eq!(flips, [True, False, False, False, Unknown, Unknown, True, True]);
+    }
+
+    #[test]
+    fn late_samples_rejudge_in_the_engines_mode() {
+        let ctx = solver_context();
+        let props = || [(crate::parser::parse_prop("always[2](P <= 120)").unwrap(), 3)];
+        let sample = |p| HashMap::from([(Var::P, p)]);
+        for mode in [EngineMode::Sat, EngineMode::Rust] {
+            let mut engine = Engine::new(&ctx, mode, props()).unwrap();
+            assert_eq!(engine.tick(0, sample(130.0))[0].truth, Truth::False);
+            // The FAIL is held; a harmless late sample must not turn it to PASS
+            let v = engine.insert_late(1, sample(100.0)).remove(0);
+            assert_eq!((v.truth, v.source), (Truth::False, mode));
+            assert_eq!(v.core.is_empty(), mode == EngineMode::Rust);
+
+            let mut engine = Engine::new(&ctx, mode, props()).unwrap();
+            engine.tick(0, sample(100.0));
+            assert_eq!(engine.tick(20, sample(100.0))[0].truth, Truth::True);
+            // a late violation FAILs the newest tick, and later ticks keep it
+            assert_eq!(engine.insert_late(10, sample(130.0))[0].truth, Truth::False);
+            assert_eq!(engine.tick(30, sample(100.0))[0].truth, Truth::False);
+            assert_eq!(engine.timestamps(), [30, 20, 10]);
+        }
+    }
+}
@@ -1,9 +1,22 @@
+pub mod cert;
+pub mod checkpoint;
 pub mod cnf;
 pub mod cnf_tseitin;
 pub mod dsl;
+pub mod engine;
 pub mod monitor;
+pub mod monitor_set;
+pub mod pack;
+pub mod parser;
+pub mod pipeline;
+pub mod reorder;
 pub mod sat;
+pub mod station;
+pub mod tags;
+pub mod transport;
+pub mod witness;
 
 // Re-export commonly used types
 pub use dsl::{Prop, Var};
-pub use monitor::PropertyMonitor; 
\ No newline at end of file
+pub use monitor::PropertyMonitor;
+pub use monitor_set::MonitorSet; 
\ No newline at end of file
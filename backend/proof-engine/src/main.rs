@@ -2,87 +2,105 @@
 // =============================================================
 // 1. **Zero‑copy JSON parsing** with `simd‑json` borrowed API → 3‑4× faster.
 // 2. **Pre‑allocated VecDeque** window (capacity = horizon) – no realloc.
-// 3. **Static tag lookup table** (`TAG_TO_VAR`) avoids match chains.
-// 4. All other logic unchanged; proof packets now produced at ~15 µs/step
-//    (50 constraints, 6‑sample window, MacBook M3) – well below 200 ms SLA.
+// 3. **Interned tag registry** (`tags`) maps tag names to compact ids;
+//    unknown tags are reported instead of silently dropped.
+// 4. Properties come from a TOML property pack (`PROPERTY_PACK`), each with
+//    a stable id, severity and its own horizon.
+// 5. Packs hot-reload: the file is polled and swapped between messages,
+//    with a `PACK_VERSION_CHANGED` event on the proof topic.  An edit that
+//    keeps the old `version` is rejected, so a version names one rule set.
+// 6. Verdicts come from the SAT monitors (`ENGINE_MODE=sat`, default) or
+//    the pure-Rust evaluator (`ENGINE_MODE=rust`); packets say which, with
+//    the UNSAT core and solver time.  Both hold a violation while its tick
+//    is inside the horizon, so they flip PASS/FAIL on the same tick.
+// 7. `cert_hash` is the real certificate hash (`cert`, via libsentinel_ffi)
+//    of the property and the window it was judged on, timestamps included.
+// 8. `trace_hash` follows the canonical `sentinel_trace_hash` encoding of
+//    the judged window, timestamps included.
+// 9. FAIL packets carry a `witness`: the failing sub-formula, its tags and
+//    the samples it read (`witness::explain`).
+// 10. Packets carry the STL robustness margin (`dsl::robustness`) of the
+//     judged window.  A property's `alarm_margin` also publishes a PASS
+//     packet (`margin_alarm`) when the margin drops below it or recovers,
+//     so shrinking margins alarm before a violation.
+// 11. Timestamps keep sub-second resolution (`ts` may be fractional
+//     seconds); `start_ts`/`end_ts` are the oldest and newest samples of
+//     the judged window.
+// 12. Horizons are sample counts (`6`) or durations (`30s`), and `|dX|`
+//     bounds are per second of sample time.
+// 13. Samples are reordered by timestamp behind a watermark
+//     (`ALLOWED_LATENESS`, default 0s); samples later than that follow
+//     `LATE_POLICY`: `drop`, `reevaluate` the window, or publish
+//     `correct`ion packets (`reorder`).
+// 14. Missing readings are not zeros: verdicts are PASS / FAIL /
+//     INCONCLUSIVE, with each property's `missing` policy (hold last
+//     value, violation, unknown) applied in both engine modes.
+// 15. Traces are keyed by asset (`asset` payload field, else the Kafka
+//     key): each asset has its own window and verdict state, runs the
+//     properties bound to its class, and is named in its packets.
+//     At most `MAX_ASSETS` (default 4096) are judged at once; the least
+//     recently seen asset is retired to admit a new one.
+// 16. Delivery into the proof topic is exactly-once: trace messages'
+//     packets and their consumer offsets commit in one Kafka transaction
+//     (idempotent producer, `KAFKA_TRANSACTIONAL_ID`, no auto-commit), so
+//     a `read_committed` consumer sees each verdict change once.  A
+//     transaction takes up to `TXN_MESSAGES` messages (default 100) or
+//     `TXN_LINGER` after the first (default 100ms).  A crash aborts the
+//     open transaction and the restart resumes at the last committed
+//     offset; without checkpoints that offset stays at the oldest sample
+//     still held for reordering.  Each running instance needs its own
+//     transactional id.
+// 17. Each transaction also carries a checkpoint of the touched asset
+//     (`checkpoint::Snapshot`: window, reorder buffer, published verdicts;
+//     versioned and checksummed) to the compacted `KAFKA_CHECKPOINT_TOPIC`.
+//     Boot restores the latest committed snapshot per asset, so windows
+//     and verdicts resume exactly at the committed offset.  A retired
+//     asset's snapshot is tombstoned, and boot restores at most
+//     `MAX_ASSETS`, the most recently sampled, tombstoning the rest.
+// 18. The loop (`pipeline`) is transport-agnostic: `TRACE_SOURCE` and
+//     `PROOF_SINK` pick `kafka` (default), `stdio` or a JSONL
+//     `file:<path>` (`transport`), so the engine runs without a broker,
+//     e.g. `TRACE_SOURCE=file:trace.jsonl PROOF_SINK=stdio`.  Items 16-17
+//     need Kafka on both ends.  A Kafka source needs the Kafka sink, the
+//     only one that commits its offsets; other pairings are rejected.
 // =============================================================
 
-mod monitor;
-mod dsl;
-mod cnf;
-mod sat;
-
-use simd_json::prelude::*;
-use rdkafka::Message;
-use blake3::Hasher;
-use chrono::{DateTime, Utc};
-use dsl::{Prop, Trace, Var};
-use rdkafka::config::ClientConfig;
-use rdkafka::consumer::{Consumer, StreamConsumer};
-use rdkafka::message::BorrowedMessage;
-use rdkafka::producer::{FutureProducer, FutureRecord};
-use serde::Serialize;
-use simd_json::BorrowedValue;
-use std::collections::{HashMap, VecDeque};
-use once_cell::sync::Lazy;
-
-#[derive(Serialize)]
-struct ProofPacket {
-    property_id: String,
-    start_ts: i64,
-    end_ts: i64,
-    trace_hash: String,
-    cert_hash: String,
-    verdict: &'static str,
-}
-
-// ------------------------------------------------------------------
-// Fast zero‑copy parse using `simd-json` BorrowedValue.
-// Expected payload: {"ts":1688145051,"tags":{"P":75.2,"T":24.1}}
-// Returns (timestamp, Sample).
-// ------------------------------------------------------------------
-static TAG_TO_VAR: Lazy<HashMap<&'static str, Var>> = Lazy::new(|| {
-    use Var::*;
-    HashMap::from([
-        ("P", P),
-        ("T", T),
-        ("Flow", Flow),
-        ("Valve", Valve),
-    ])
-});
-
-#[inline]
-fn parse_trace(msg: &BorrowedMessage) -> Option<(DateTime<Utc>, HashMap<Var, f64>)> {
-    let payload = msg.payload()?;
-    // Safety: simd-json expects &mut [u8]
-    let mut buf = payload.to_vec();
-    let v: BorrowedValue<'_> = simd_json::to_borrowed_value(&mut buf).ok()?;
-    let obj = v.as_object()?;
-    let ts_val = obj.get("ts")?.as_i64()?;
-    let tags = obj.get("tags")?.as_object()?;
-
-    let mut sample = HashMap::with_capacity(tags.len());
-    for (k, v) in tags.iter() {
-        if let Some(&var) = TAG_TO_VAR.get(k.as_ref()) {
-            if let Some(f) = v.as_f64() {
-                sample.insert(var, f);
-            }
+use proof_engine::engine::EngineMode;
+use proof_engine::monitor::{parse_duration, Horizon};
+use proof_engine::pack::PropertyPack;
+use proof_engine::pipeline::{self, Settings};
+use proof_engine::reorder::LatePolicy;
+use proof_engine::tags;
+use proof_engine::transport::{Endpoint, KafkaConfig};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::watch;
+
+/// Poll the pack file's mtime and publish every new pack that validates
+/// and carries a new version; a rejected edit is logged and the running
+/// pack stays in force.
+async fn watch_pack(path: String, default_horizon: Horizon, every: Duration, tx: watch::Sender<Arc<PropertyPack>>) {
+    let mtime = |p: &str| std::fs::metadata(p).and_then(|m| m.modified()).ok();
+    let mut last = mtime(&path);
+    let mut interval = tokio::time::interval(every);
+    loop {
+        interval.tick().await;
+        let now = mtime(&path);
+        if now == last {
+            continue;
         }
-    }
-    let ts = DateTime::<Utc>::from_timestamp(ts_val, 0)?;
-    Some((ts, sample))
-}
-
-#[inline]
-fn hash_trace(trace: &Trace) -> String {
-    let mut hasher = Hasher::new();
-    for sample in trace {
-        for (v, val) in sample {
-            hasher.update(&[*v as u8]);
-            hasher.update(&val.to_le_bytes());
+        last = now;
+        let loaded = PropertyPack::load(&path, default_horizon);
+        match loaded.and_then(|pack| Ok(pack.replaces(&tx.borrow())?.then_some(pack))) {
+            Ok(None) => {}
+            Ok(Some(pack)) => {
+                if tx.send(Arc::new(pack)).is_err() {
+                    return;
+                }
+            }
+            Err(e) => log::error!("property pack reload rejected, keeping current pack: {e}"),
         }
     }
-    hex::encode(hasher.finalize().as_bytes())
 }
 
 #[tokio::main]
@@ -90,63 +108,78 @@ async fn main() -> anyhow::Result<()> {
     dotenvy::dotenv().ok();
     env_logger::init();
 
-    let brokers = std::env::var("KAFKA_BROKERS").unwrap_or_else(|_| "localhost:9092".into());
-    let trace_topic = std::env::var("KAFKA_TRACE_TOPIC").unwrap_or_else(|_| "plc.trace".into());
-    let _proof_topic = std::env::var("KAFKA_PROOF_TOPIC").unwrap_or_else(|_| "sentinel.proofs".into());
-    let horizon: usize = std::env::var("WINDOW_HORIZON").unwrap_or_else(|_| "6".into()).parse()?;
-
-    // 50 identical pressure‑bound constraints for demo
-    let props: Vec<Prop> = (0..50).map(|_| Prop::Le(Var::P, 120.0)).collect();
-    let mut prev_verdicts = vec![true; props.len()];
-
-    // Pre‑allocated ring buffer
-    let mut window: VecDeque<HashMap<Var, f64>> = VecDeque::with_capacity(horizon);
-
-    // Kafka consumer / producer
-    let consumer: StreamConsumer = ClientConfig::new()
-        .set("bootstrap.servers", &brokers)
-        .set("group.id", "sentinel-monitor")
-        .create()?;
-    consumer.subscribe(&[&trace_topic])?;
-    let producer: FutureProducer = ClientConfig::new().set("bootstrap.servers", &brokers).create()?;
-
-    while let Ok(msg) = consumer.recv().await {
-        let (ts, sample) = match parse_trace(&msg) { Some(t) => t, None => continue };
-
-        window.push_front(sample);
-        if window.len() > horizon { window.pop_back(); }
-        let trace_vec: Trace = window.iter().cloned().collect();
-
-        // Simple property evaluation for now
-        let verdicts: Vec<bool> = props.iter().map(|prop| dsl::eval_prop(prop, &trace_vec)).collect();
-        
-        for (i, &v) in verdicts.iter().enumerate() {
-            if v != prev_verdicts[i] {
-                let _prop_json = serde_json::to_string(&props[i])?;
-                let _trace_json = serde_json::to_string(&trace_vec)?;
-                // Placeholder for cert hash - replace with actual implementation
-                let cert_hash = format!("placeholder_{}", i);
-                let packet = ProofPacket {
-                    property_id: format!("prop_{}", i),
-                    start_ts: ts.timestamp() - 5,
-                    end_ts: ts.timestamp(),
-                    trace_hash: hash_trace(&trace_vec),
-                    cert_hash,
-                    verdict: if v { "PASS" } else { "FAIL" },
-                };
-                let payload = serde_json::to_vec(&packet)?;
-                producer
-                    .send(
-                        FutureRecord::<(), _>::to("sentinel.proofs").payload(&payload),
-                        std::time::Duration::from_secs(0),
-                    )
-                    .await
-                    .map_err(|(e, _)| e)?;
-            }
-        }
-        prev_verdicts = verdicts;
+    let source: Endpoint = std::env::var("TRACE_SOURCE")
+        .unwrap_or_else(|_| "kafka".into())
+        .parse()
+        .map_err(anyhow::Error::msg)?;
+    let sink: Endpoint = std::env::var("PROOF_SINK")
+        .unwrap_or_else(|_| "kafka".into())
+        .parse()
+        .map_err(anyhow::Error::msg)?;
+    if source == Endpoint::Kafka && sink != Endpoint::Kafka {
+        anyhow::bail!("TRACE_SOURCE=kafka needs PROOF_SINK=kafka: only the Kafka sink commits consumer offsets");
     }
-    Ok(())
+    let kafka = KafkaConfig {
+        brokers: std::env::var("KAFKA_BROKERS").unwrap_or_else(|_| "localhost:9092".into()),
+        trace_topic: std::env::var("KAFKA_TRACE_TOPIC").unwrap_or_else(|_| "plc.trace".into()),
+        proof_topic: std::env::var("KAFKA_PROOF_TOPIC").unwrap_or_else(|_| "sentinel.proofs".into()),
+        group_id: "sentinel-monitor".into(),
+        transactional_id: std::env::var("KAFKA_TRANSACTIONAL_ID")
+            .unwrap_or_else(|_| "sentinel-proof-engine".into()),
+        // Empty disables checkpointing
+        checkpoint_topic: Some(
+            std::env::var("KAFKA_CHECKPOINT_TOPIC").unwrap_or_else(|_| "sentinel.checkpoints".into()),
+        )
+        .filter(|t| !t.is_empty()),
+    };
+    let default_horizon: Horizon = std::env::var("WINDOW_HORIZON")
+        .unwrap_or_else(|_| "6".into())
+        .parse()
+        .map_err(anyhow::Error::msg)?;
+    let reload_secs: u64 = std::env::var("PACK_RELOAD_SECS").unwrap_or_else(|_| "5".into()).parse()?;
+    let mode: EngineMode = std::env::var("ENGINE_MODE")
+        .unwrap_or_else(|_| "sat".into())
+        .parse()
+        .map_err(anyhow::Error::msg)?;
+    let lateness_ns = parse_duration(&std::env::var("ALLOWED_LATENESS").unwrap_or_else(|_| "0s".into()))
+        .map_err(anyhow::Error::msg)?;
+    let late_policy: LatePolicy = std::env::var("LATE_POLICY")
+        .unwrap_or_else(|_| "drop".into())
+        .parse()
+        .map_err(anyhow::Error::msg)?;
+    let max_assets: usize = std::env::var("MAX_ASSETS").unwrap_or_else(|_| "4096".into()).parse()?;
+    let txn_messages: usize = std::env::var("TXN_MESSAGES").unwrap_or_else(|_| "100".into()).parse()?;
+    let txn_linger = parse_duration(&std::env::var("TXN_LINGER").unwrap_or_else(|_| "100ms".into()))
+        .map_err(anyhow::Error::msg)?;
+    if let Ok(path) = std::env::var("TAG_REGISTRY") {
+        let n = tags::load_config(&path)?;
+        log::info!("tag registry: {n} tags from {path}");
+    }
+
+    let pack_path = std::env::var("PROPERTY_PACK").unwrap_or_else(|_| "packs/default.toml".into());
+    let pack = Arc::new(PropertyPack::load(&pack_path, default_horizon)?);
+    log::info!(
+        "property pack {} ({} properties, {} assets) from {pack_path}",
+        pack.version, pack.properties.len(), pack.assets.len()
+    );
+
+    let (pack_tx, pack_rx) = watch::channel(pack);
+    if reload_secs > 0 {
+        tokio::spawn(watch_pack(pack_path.clone(), default_horizon, Duration::from_secs(reload_secs), pack_tx));
+    }
+
+    log::info!("{mode} engine, traces from {source}, proofs to {sink}");
+    let settings = Settings {
+        mode,
+        allowed_lateness_ns: lateness_ns,
+        late_policy,
+        max_assets,
+        txn_messages: txn_messages.max(1),
+        txn_linger: Duration::from_nanos(txn_linger as u64),
+    };
+    let mut source = source.source(&kafka).await?;
+    let mut sink = sink.sink(&kafka).await?;
+    pipeline::run(&mut source, &mut sink, settings, pack_rx).await
 }
 
 
@@ -1,61 +1,531 @@
 // proof-engine/src/monitor.rs  (v0.3 – Tseitin + UNSAT core)
 // =============================================================
 // * Integrates `delta_clauses_tseitin` for pure Boolean props (¬implWithin/¬windowAll).
-// * Exposes `last_core` with clause indices for audit UI.
+// * Keeps one `SatCore` alive per property: clauses age out after H ticks,
+//   or once their sample leaves a time-based horizon (`Horizon::Span`).
+// * Exposes `last_core` with clause indices for audit UI, and
+//   `last_core_origins` mapping each index to the tick and `Prop` sub-node
+//   that produced it.
+// * `tick_with` takes atom verdicts from the caller so a `MonitorSet` can
+//   share them between properties.
+// * Verdicts are three-valued: missing readings follow the property's
+//   `Missing` policy and can make a tick `Truth::Unknown` (INCONCLUSIVE).
+// * A horizon must hold at least one sample; construction fails otherwise.
+// * A span holding fewer samples than the property reads
+//   (`Prop::history`) judges the tick INCONCLUSIVE instead of letting a
+//   short window pass vacuously.
+// * Each tick's own verdict is kept beside its batch, and `eval_with`
+//   holds Rust-evaluator verdicts over the same ticks, so both engine modes
+//   flip PASS/FAIL on the same tick.
 // =============================================================
 
-use crate::cnf::delta_clauses;               // fallback encoder
-use crate::dsl::{Prop, Trace};
-use crate::sat::{SatCore, SatResult};
+use crate::cnf_tseitin::{ClauseOrigin, Encoding, Leaf, TseitinEncoder};
+use crate::dsl::{eval_truth, Missing, Prop, Sample, Truth};
+use crate::sat::{Clause, Lit, SatCore, SatResult};
+use std::collections::VecDeque;
+use std::fmt;
+use std::str::FromStr;
+use thiserror::Error;
 use z3::{Config, Context};
 
-pub struct PropertyMonitor {
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MonitorError {
+    #[error("horizon must hold at least one sample")]
+    EmptyHorizon,
+}
+
+/// How much of the trace a property is judged on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Horizon {
+    /// The newest `n` samples.
+    Samples(usize),
+    /// Samples taken at most this many ns before the newest one.
+    Span(i64),
+}
+
+impl From<usize> for Horizon {
+    fn from(n: usize) -> Self {
+        Horizon::Samples(n)
+    }
+}
+
+/// A property as monitored: formula, horizon and missing-data policy.
+/// Built from `(prop, horizon)` (policy `Missing::Unknown`) or
+/// `(prop, horizon, missing)`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Monitored {
+    pub prop: Prop,
+    pub horizon: Horizon,
+    pub missing: Missing,
+}
+
+impl<H: Into<Horizon>> From<(Prop, H)> for Monitored {
+    fn from((prop, horizon): (Prop, H)) -> Self {
+        Monitored { prop, horizon: horizon.into(), missing: Missing::default() }
+    }
+}
+
+impl<H: Into<Horizon>> From<(Prop, H, Missing)> for Monitored {
+    fn from((prop, horizon, missing): (Prop, H, Missing)) -> Self {
+        Monitored { prop, horizon: horizon.into(), missing }
+    }
+}
+
+const UNITS: [(&str, i64); 6] =
+    [("h", 3_600_000_000_000), ("m", 60_000_000_000), ("s", 1_000_000_000), ("ms", 1_000_000), ("us", 1_000), ("ns", 1)];
+
+impl Horizon {
+    /// How many of the newest samples fall inside the horizon, given their
+    /// timestamps newest-first.
+    pub fn window_len(&self, stamps: &[i64]) -> usize {
+        match *self {
+            Horizon::Samples(n) => n.min(stamps.len()),
+            Horizon::Span(ns) => stamps.iter().take_while(|&&t| stamps[0].saturating_sub(t) <= ns).count(),
+        }
+    }
+
+    /// `self`, unless it is a horizon of no samples.  A span always holds
+    /// the newest sample.
+    pub fn validate(self) -> Result<Self, MonitorError> {
+        match self {
+            Horizon::Samples(0) => Err(MonitorError::EmptyHorizon),
+            h => Ok(h),
+        }
+    }
+}
+
+/// `6` is six samples; `30s`, `500ms`, `2m`, `1h` (also `us`, `ns`) are
+/// durations.
+impl FromStr for Horizon {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s.bytes().all(|b| b.is_ascii_digit()) {
+            let n = s.parse().map_err(|_| format!("invalid horizon {s:?} (expected e.g. `6` or `30s`)"))?;
+            return Horizon::Samples(n).validate().map_err(|e| format!("horizon {s:?}: {e}"));
+        }
+        parse_duration(s).map(Horizon::Span).map_err(|e| format!("horizon {s:?}: {e}"))
+    }
+}
+
+/// Parse a duration such as `30s`, `500ms`, `2m` or `1h` (also `us`, `ns`)
+/// into ns.
+pub fn parse_duration(s: &str) -> Result<i64, String> {
+    let s = s.trim();
+    let split = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let (num, unit) = s.split_at(split);
+    let n: u64 = num.parse().map_err(|_| format!("invalid duration {s:?} (expected e.g. `30s`)"))?;
+    let (_, scale) = UNITS
+        .iter()
+        .find(|(u, _)| *u == unit.trim())
+        .ok_or_else(|| format!("unknown unit {unit:?} in {s:?} (use h, m, s, ms, us or ns)"))?;
+    i64::try_from(n).ok().and_then(|n| n.checked_mul(*scale)).ok_or_else(|| format!("duration {s:?} is too long"))
+}
+
+impl fmt::Display for Horizon {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Horizon::Samples(n) => write!(f, "{n}"),
+            Horizon::Span(ns) => {
+                let (unit, scale) = UNITS.iter().find(|(_, scale)| ns % scale == 0).expect("ns divides");
+                write!(f, "{}{unit}", ns / scale)
+            }
+        }
+    }
+}
+
+/// Z3 context with the monitor's default 100 ms timeout.  Owned by the
+/// caller and shared by every monitor borrowing it.
+pub fn solver_context() -> Context {
+    let mut cfg = Config::new();
+    cfg.set_timeout_msec(100);
+    Context::new(&cfg)
+}
+
+/// Long-lived monitor for one property.  The clause window inside `sat`
+/// spans the ticks whose samples are inside the horizon, so a violation
+/// keeps the solver UNSAT until its tick ages out.
+pub struct PropertyMonitor<'ctx> {
     prop: Prop,
-    horizon: usize,
-    ctx: Context,
+    horizon: Horizon,
+    missing: Missing,
+    sat: SatCore<'ctx>,
+    encoder: TseitinEncoder,
+    /// Clause origins per tick, parallel to the batches held by `sat`.
+    origins: VecDeque<Vec<ClauseOrigin>>,
+    /// Per tick, the literal saying its window proves the property
+    /// (`Encoding::proved`), parallel to `origins`.
+    proved: VecDeque<Option<Lit>>,
+    /// Per tick in the clause window, oldest first, what its window alone
+    /// says (after the missing-data policy).
+    verdicts: VecDeque<Truth>,
+    /// Number of ticks processed so far (stamped into clause origins).
+    ticks: u64,
     pub last_core: Vec<usize>,     // indices of UNSAT core (for UI)
+    pub last_core_origins: Vec<ClauseOrigin>, // parallel to `last_core`
 }
 
-impl PropertyMonitor {
-    pub fn new(prop: Prop, horizon: usize) -> Self {
-        let mut cfg = Config::new();
-        cfg.set_timeout_msec(100);
-        let ctx = Context::new(&cfg);
-        PropertyMonitor {
+impl<'ctx> PropertyMonitor<'ctx> {
+    pub fn new(ctx: &'ctx Context, prop: Prop, horizon: impl Into<Horizon>) -> Result<Self, MonitorError> {
+        let horizon = horizon.into().validate()?;
+        // A span's window is resized every tick.
+        let ticks = match horizon {
+            Horizon::Samples(n) => n,
+            Horizon::Span(_) => usize::MAX,
+        };
+        Ok(PropertyMonitor {
             prop,
             horizon,
-            ctx,
+            missing: Missing::default(),
+            sat: SatCore::new(ctx, usize::MAX).batch_window(ticks),
+            encoder: TseitinEncoder::new(),
+            origins: VecDeque::new(),
+            proved: VecDeque::new(),
+            verdicts: VecDeque::new(),
+            ticks: 0,
             last_core: Vec::new(),
-        }
+            last_core_origins: Vec::new(),
+        })
+    }
+
+    /// Set the missing-data policy (default `Missing::Unknown`).
+    pub fn with_missing(mut self, missing: Missing) -> Self {
+        self.missing = missing;
+        self
+    }
+
+    pub fn prop(&self) -> &Prop {
+        &self.prop
+    }
+
+    pub fn horizon(&self) -> Horizon {
+        self.horizon
+    }
+
+    pub fn missing(&self) -> Missing {
+        self.missing
     }
 
     fn is_boolean_only(p: &Prop) -> bool {
         use Prop::*;
         match p {
-            And(a,b)|Or(a,b) => Self::is_boolean_only(a)&&Self::is_boolean_only(b),
-            Le(_,_)|RateBound(_,_) => true,
+            And(a,b)|Or(a,b)|Implies(a,b) => Self::is_boolean_only(a)&&Self::is_boolean_only(b),
+            Not(a) => Self::is_boolean_only(a),
+            Le(..)|Ge(..)|Lt(..)|Gt(..)|Eq(..)|Ne(..)|RateBound(..) => true,
+            WindowAll(_,_)|ImplWithin(_,_,_) => false,
         }
     }
 
-    pub fn tick(&mut self, window: &Trace) -> bool {
-        debug_assert!(window.len() <= self.horizon);
-        let _holds = crate::dsl::eval_prop(&self.prop, window); // helper bridging to Rust monitor
-        let delta = if Self::is_boolean_only(&self.prop) {
-            // For now, use the fallback encoder
-            delta_clauses(&self.prop, window)
+    /// Core of the last violation as `(tick, sub-formula)` pairs.
+    pub fn core_nodes(&self) -> Vec<(u64, &Prop)> {
+        self.last_core_origins
+            .iter()
+            .filter_map(|o| self.prop.node(o.node).map(|p| (o.tick, p)))
+            .collect()
+    }
+
+    pub fn tick(&mut self, window: &[Sample]) -> Truth {
+        let missing = self.missing;
+        self.tick_with(window, &[], &mut |_, p, w, ts| eval_truth(p, w, ts, missing))
+    }
+
+    /// `tick` on a timestamped window (`stamps` in ns, parallel to
+    /// `window`, or empty), with atom truth values supplied by `leaf` (see
+    /// `TseitinEncoder::encode_traced_with`).  Temporal properties ask `leaf`
+    /// about their root.
+    ///
+    /// With a `Span` horizon, `window` must be exactly the samples inside
+    /// the span: it sets how many ticks stay in the clause window.
+    ///
+    /// `False` while a tick in the clause window refutes the property,
+    /// else `Unknown` while one fails to prove it, else `True`.  Under
+    /// `Missing::Violation` an unproved tick refutes.
+    pub fn tick_with(&mut self, window: &[Sample], stamps: &[i64], leaf: Leaf<'_>) -> Truth {
+        let keep = self.keep(window);
+        if let Horizon::Span(_) = self.horizon {
+            self.sat.set_batch_window(keep);
+        }
+        let tick = self.ticks;
+        self.ticks += 1;
+        let short = self.too_short(window);
+        let truth = if short { Truth::Unknown } else { leaf(0, &self.prop, window, stamps) };
+        self.record(self.missing.resolve(truth), keep);
+        let mut delta = if short {
+            self.encoder.undecided(tick)
+        } else if Self::is_boolean_only(&self.prop) {
+            self.encoder.encode_traced_with(&self.prop, window, stamps, tick, leaf)
         } else {
-            delta_clauses(&self.prop, window) // earlier empty‑clause strategy
+            // earlier empty‑clause strategy (`cnf::delta_clauses`)
+            match truth {
+                Truth::True => Encoding::default(),
+                Truth::False => Encoding::untracked(vec![Clause(Vec::new())], tick),
+                Truth::Unknown => self.encoder.undecided(tick),
+            }
         };
-        
-        // Create a new SatCore for this operation
-        let mut sat = SatCore::new(&self.ctx, self.horizon);
-        match sat.unsat_recycle(delta).expect("solver") {
-            SatResult::Sat => { self.last_core.clear(); true },
+        if self.missing == Missing::Violation {
+            // Demand a proof, not just the absence of a refutation.
+            if let Some(t) = delta.proved.take() {
+                delta.push(Clause(vec![t]), tick, 0);
+            }
+        }
+        self.solve(delta, keep)
+    }
+
+    /// Add `delta` as the newest tick's batch and solve the clause window.
+    fn solve(&mut self, delta: Encoding, keep: usize) -> Truth {
+        // Mirror the batch eviction done inside `SatCore`.
+        while self.origins.len() >= keep {
+            self.origins.pop_front();
+            self.proved.pop_front();
+        }
+        self.origins.push_back(delta.origins);
+        self.proved.push_back(delta.proved);
+
+        match self.sat.unsat_recycle(delta.clauses).expect("solver") {
+            SatResult::Sat => {
+                self.last_core.clear();
+                self.last_core_origins.clear();
+                let sat = &self.sat;
+                if self.proved.iter().flatten().all(|&t| sat.model_value(t) == Some(true)) {
+                    Truth::True
+                } else {
+                    Truth::Unknown
+                }
+            },
             SatResult::Unsat => {
-                self.last_core = sat.get_unsat_core().unwrap_or_default();
-                false
+                self.last_core = self.sat.get_unsat_core().unwrap_or_default();
+                let live: Vec<ClauseOrigin> = self.origins.iter().flatten().copied().collect();
+                self.last_core_origins = self.last_core.iter().map(|&i| live[i]).collect();
+                Truth::False
             },
-            SatResult::Unknown => { log::warn!("Z3 UNKNOWN"); false },
+            SatResult::Unknown => { log::warn!("Z3 UNKNOWN"); Truth::Unknown },
+        }
+    }
+
+    /// Rust-evaluator counterpart of `tick_with`: the window is judged by
+    /// `leaf` alone and the verdict is the worst over the ticks the clause
+    /// window would hold, so it flips on the same tick as the solver's.
+    pub fn eval_with(&mut self, window: &[Sample], stamps: &[i64], leaf: Leaf<'_>) -> Truth {
+        let keep = self.keep(window);
+        self.ticks += 1;
+        let truth = if self.too_short(window) { Truth::Unknown } else { leaf(0, &self.prop, window, stamps) };
+        self.record(self.missing.resolve(truth), keep);
+        self.held()
+    }
+
+    /// Take up where a monitor whose `history` was `history` left off: one
+    /// tick per verdict, oldest first, on a fresh monitor.  With `solver`,
+    /// each tick also gets a clause batch that makes the solver agree with
+    /// its verdict, as `tick_with` needs; `eval_with` does without.
+    pub fn resume(&mut self, history: &[Truth], solver: bool) {
+        let keep = match self.horizon {
+            Horizon::Samples(n) => n,
+            Horizon::Span(_) => history.len().max(1),
+        };
+        if let Horizon::Span(_) = self.horizon {
+            self.sat.set_batch_window(keep);
+        }
+        for &truth in history {
+            let tick = self.ticks;
+            self.ticks += 1;
+            self.record(truth, keep);
+            if solver {
+                let delta = match truth {
+                    Truth::True => Encoding::default(),
+                    Truth::False => Encoding::untracked(vec![Clause(Vec::new())], tick),
+                    Truth::Unknown => self.encoder.undecided(tick),
+                };
+                self.solve(delta, keep);
+            }
+        }
+    }
+
+    /// Own verdicts of the ticks in the clause window, oldest first: what
+    /// `held` is taken over.
+    pub fn history(&self) -> Vec<Truth> {
+        self.verdicts.iter().copied().collect()
+    }
+
+    /// Forget the newest tick, so the next `tick_with` or `eval_with` judges
+    /// it again, e.g. with a late sample slotted into its window.
+    pub fn undo_tick(&mut self) {
+        if self.ticks == 0 {
+            return;
+        }
+        self.ticks -= 1;
+        self.sat.pop_batch();
+        self.origins.pop_back();
+        self.proved.pop_back();
+        self.verdicts.pop_back();
+    }
+
+    /// Verdict of `window` on its own, as one tick of `eval_with` sees it.
+    pub fn judge(&self, window: &[Sample], stamps: &[i64]) -> Truth {
+        if self.too_short(window) {
+            return self.missing.resolve(Truth::Unknown);
         }
+        self.missing.resolve(eval_truth(&self.prop, window, stamps, self.missing))
+    }
+
+    /// The held verdict had the newest tick seen `window` instead; the
+    /// monitor is left as it is.
+    pub fn rejudged(&self, window: &[Sample], stamps: &[i64]) -> Truth {
+        let keep = self.keep(window);
+        self.verdicts.iter().rev().skip(1).take(keep - 1).fold(self.judge(window, stamps), |a, &b| a.min(b))
+    }
+
+    /// A span window with fewer samples than the property reads: a coarse
+    /// cadence or a gap would otherwise make it pass vacuously.
+    fn too_short(&self, window: &[Sample]) -> bool {
+        matches!(self.horizon, Horizon::Span(_)) && window.len() < self.prop.history()
+    }
+
+    /// Worst verdict among the ticks in the clause window (`True` before the
+    /// first tick).
+    pub fn held(&self) -> Truth {
+        self.verdicts.iter().copied().min().unwrap_or(Truth::True)
+    }
+
+    /// Ticks the clause window holds once `window` is the newest: `n` for
+    /// `Samples(n)`, one per sample inside a span.
+    fn keep(&self, window: &[Sample]) -> usize {
+        match self.horizon {
+            Horizon::Samples(n) => {
+                debug_assert!(window.len() <= n);
+                n
+            }
+            Horizon::Span(_) => window.len().max(1),
+        }
+    }
+
+    fn record(&mut self, truth: Truth, keep: usize) {
+        while self.verdicts.len() >= keep {
+            self.verdicts.pop_front();
+        }
+        self.verdicts.push_back(truth);
+    }
+}
+
+// ---------------------------
+// Unit tests
+// ---------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dsl::Var;
+    use std::collections::HashMap;
+
+    #[test]
+    fn core_points_at_failed_conjunct() {
+        let ok = Prop::Le(Var::T, 50.0);
+        let bad = Prop::Le(Var::P, 120.0);
+        let ctx = solver_context();
+        let mut mon = PropertyMonitor::new(&ctx, Prop::And(Box::new(ok), Box::new(bad.clone())), 6).unwrap();
+        let good = HashMap::from([(Var::P, 100.0), (Var::T, 20.0)]);
+        let sample = HashMap::from([(Var::P, 130.0), (Var::T, 20.0)]);
+        assert_eq!(mon.tick(&[good]), Truth::True);
+        assert_eq!(mon.tick(&[sample]), Truth::False);
+        let nodes = mon.core_nodes();
+        assert!(nodes.contains(&(1, &bad)), "{nodes:?}");
+        assert!(!nodes.iter().any(|(_, p)| **p == Prop::Le(Var::T, 50.0)));
+    }
+
+    #[test]
+    fn violation_persists_for_horizon() {
+        let ctx = solver_context();
+        let horizon = 3;
+        assert!(PropertyMonitor::new(&ctx, Prop::Le(Var::P, 120.0), 0).is_err());
+        let mut mon = PropertyMonitor::new(&ctx, Prop::Le(Var::P, 120.0), horizon).unwrap();
+        let high = HashMap::from([(Var::P, 130.0)]);
+        let low = HashMap::from([(Var::P, 100.0)]);
+        assert_eq!(mon.tick(&[high]), Truth::False);
+        // The violating tick stays in the clause window for H ticks in total …
+        for _ in 1..horizon {
+            assert_eq!(mon.tick(std::slice::from_ref(&low)), Truth::False);
+            let nodes = mon.core_nodes();
+            assert!(!nodes.is_empty() && nodes.iter().all(|n| *n == (0, mon.prop())), "{nodes:?}");
+        }
+        // … then ages out.
+        assert_eq!(mon.tick(std::slice::from_ref(&low)), Truth::True);
+        assert!(mon.last_core.is_empty());
+        // re-judging the newest tick replaces its batch
+        mon.undo_tick();
+        assert_eq!(mon.tick(&[HashMap::from([(Var::P, 125.0)])]), Truth::False);
+        mon.undo_tick();
+        assert_eq!(mon.tick(&[low]), Truth::True);
+    }
+
+    #[test]
+    fn horizons_parse_and_select_by_time() {
+        const SEC: i64 = 1_000_000_000;
+        assert_eq!("6".parse(), Ok(Horizon::Samples(6)));
+        assert_eq!("30s".parse(), Ok(Horizon::Span(30 * SEC)));
+        assert_eq!("1500ms".parse::<Horizon>().map(|h| h.to_string()), Ok("1500ms".into()));
+        assert_eq!(Horizon::Span(120 * SEC).to_string(), "2m");
+        assert!("30 parsecs".parse::<Horizon>().is_err());
+        assert!("-1s".parse::<Horizon>().is_err());
+        assert!("0".parse::<Horizon>().is_err());
+        assert_eq!("0s".parse(), Ok(Horizon::Span(0)));
+
+        let stamps = [40 * SEC, 35 * SEC, 30 * SEC, 10 * SEC];
+        assert_eq!(Horizon::Span(10 * SEC).window_len(&stamps), 3);
+        assert_eq!(Horizon::Span(0).window_len(&stamps), 1);
+        assert_eq!(Horizon::Samples(6).window_len(&stamps), 4);
+        assert_eq!(Horizon::Span(SEC).window_len(&[]), 0);
+    }
+
+    #[test]
+    fn span_violation_ages_out_with_its_sample() {
+        const SEC: i64 = 1_000_000_000;
+        let ctx = solver_context();
+        let mut mon = PropertyMonitor::new(&ctx, Prop::Le(Var::P, 120.0), Horizon::Span(10 * SEC)).unwrap();
+        let (mut window, mut stamps) = (Vec::new(), Vec::new());
+        let mut tick = |mon: &mut PropertyMonitor, ts: i64, p: f64| {
+            window.insert(0, HashMap::from([(Var::P, p)]));
+            stamps.insert(0, ts);
+            let n = mon.horizon().window_len(&stamps);
+            mon.tick_with(&window[..n], &stamps[..n], &mut |_, p, w, ts| eval_truth(p, w, ts, Missing::Unknown))
+        };
+        assert_eq!(tick(&mut mon, 0, 130.0), Truth::False);
+        // two quick samples: the violation is still within 10 s …
+        assert_eq!(tick(&mut mon, 4 * SEC, 100.0), Truth::False);
+        assert_eq!(tick(&mut mon, 9 * SEC, 100.0), Truth::False);
+        // … and gone once 10 s have passed, however many ticks that took.
+        assert_eq!(tick(&mut mon, 11 * SEC, 100.0), Truth::True);
+
+        // A cadence coarser than the span never gives `|dP|` its two samples
+        let ramp = Prop::WindowAll(1, Box::new(Prop::RateBound(Var::P, 1.0)));
+        let mut mon = PropertyMonitor::new(&ctx, ramp, Horizon::Span(10 * SEC)).unwrap();
+        for ts in [20, 40, 60] {
+            assert_eq!(tick(&mut mon, ts * SEC, 100.0), Truth::Unknown);
+        }
+        assert_eq!(mon.judge(&window[..1], &stamps[..1]), Truth::Unknown);
+    }
+
+    #[test]
+    fn missing_policies_shape_the_verdict() {
+        let ctx = solver_context();
+        let p = Prop::And(Box::new(Prop::Le(Var::P, 120.0)), Box::new(Prop::Le(Var::T, 80.0)));
+        let full = HashMap::from([(Var::P, 100.0), (Var::T, 20.0)]);
+        let dropout = HashMap::from([(Var::T, 20.0)]);
+        let window = [dropout.clone(), full.clone()];
+        let run = |missing| {
+            let mut mon = PropertyMonitor::new(&ctx, p.clone(), 2).unwrap().with_missing(missing);
+            (mon.tick(&window), mon.last_core.len())
+        };
+        assert_eq!(run(Missing::Unknown), (Truth::Unknown, 0));
+        assert_eq!(run(Missing::HoldLast), (Truth::True, 0));
+        let (truth, core) = run(Missing::Violation);
+        assert!(truth == Truth::False && core > 0);
+
+        // an unknown tick stays INCONCLUSIVE while it is in the clause window
+        let mut mon = PropertyMonitor::new(&ctx, p.clone(), 2).unwrap();
+        assert_eq!(mon.tick(&[dropout]), Truth::Unknown);
+        assert_eq!(mon.tick(std::slice::from_ref(&full)), Truth::Unknown);
+        assert_eq!(mon.tick(&[full]), Truth::True);
+        // temporal properties are judged outside the encoding
+        let mut mon = PropertyMonitor::new(&ctx, Prop::WindowAll(1, Box::new(p)), 2).unwrap();
+        assert_eq!(mon.tick(&window), Truth::Unknown);
     }
 }
@@ -0,0 +1,463 @@
+// proof-engine/src/monitor_set.rs
+// =============================================================
+// One engine for a whole property pack.
+// -------------------------------------------------------------
+// * Owns the trace window (newest-first, as long as the longest horizon)
+//   and the sample timestamps; each property sees the prefix its own
+//   horizon covers, by sample count or by age (`Horizon`).
+// * All monitors borrow one solver `Context`.
+// * Identical properties (same formula, horizon and missing-data policy)
+//   share one monitor.
+// * Sub-formulas are hash-consed by their printed form, so a threshold atom
+//   or temporal sub-formula that several properties mention is evaluated
+//   once per tick and window suffix.
+// =============================================================
+
+use crate::dsl::{eval_truth, Missing, Sample, Truth};
+use crate::monitor::{Horizon, MonitorError, Monitored, PropertyMonitor};
+use std::collections::{HashMap, VecDeque};
+use std::time::Instant;
+use z3::Context;
+
+pub struct MonitorSet<'ctx> {
+    /// Kept contiguous after every push so it can be lent out as a slice.
+    window: VecDeque<Sample>,
+    /// Sample timestamps in ns, parallel to `window`.
+    stamps: VecDeque<i64>,
+    horizon: usize,
+    monitors: Vec<PropertyMonitor<'ctx>>,
+    /// Shared sub-formula id of every pre-order node, per monitor.
+    node_ids: Vec<Vec<usize>>,
+    /// Property index → monitor index.
+    slots: Vec<usize>,
+    shared_nodes: usize,
+    /// Wall time of each monitor's last solve, µs.
+    solve_us: Vec<u64>,
+}
+
+impl<'ctx> MonitorSet<'ctx> {
+    /// Build from `(property, horizon)` pairs or `(property, horizon,
+    /// missing)` triples; verdicts come back in the same order.  Fails on a
+    /// horizon of no samples.
+    pub fn new(
+        ctx: &'ctx Context,
+        props: impl IntoIterator<Item = impl Into<Monitored>>,
+    ) -> Result<Self, MonitorError> {
+        Self::build(ctx, props, HashMap::new())
+    }
+
+    /// Swap in a new property list.  Monitors of properties that are still
+    /// present (same formula, horizon and policy) keep their solver state,
+    /// and the window keeps its samples.  On error the set is left as it was.
+    pub fn reload(
+        &mut self,
+        ctx: &'ctx Context,
+        props: impl IntoIterator<Item = impl Into<Monitored>>,
+    ) -> Result<(), MonitorError> {
+        let props: Vec<Monitored> = props.into_iter().map(Into::into).collect();
+        for m in &props {
+            m.horizon.validate()?;
+        }
+        let reuse = std::mem::take(&mut self.monitors)
+            .into_iter()
+            .map(|m| ((m.prop().to_string(), m.horizon(), m.missing()), m))
+            .collect();
+        let mut next = Self::build(ctx, props, reuse)?;
+        next.window = std::mem::take(&mut self.window);
+        next.stamps = std::mem::take(&mut self.stamps);
+        next.evict();
+        *self = next;
+        Ok(())
+    }
+
+    fn build(
+        ctx: &'ctx Context,
+        props: impl IntoIterator<Item = impl Into<Monitored>>,
+        mut reuse: HashMap<(String, Horizon, Missing), PropertyMonitor<'ctx>>,
+    ) -> Result<Self, MonitorError> {
+        let mut monitors = Vec::new();
+        let mut node_ids = Vec::new();
+        let mut slots = Vec::new();
+        let mut by_prop: HashMap<(String, Horizon, Missing), usize> = HashMap::new();
+        let mut by_node: HashMap<String, usize> = HashMap::new();
+        for m in props {
+            let Monitored { prop, horizon, missing } = m.into();
+            let key = (prop.to_string(), horizon, missing);
+            let slot = match by_prop.get(&key) {
+                Some(&slot) => slot,
+                None => {
+                    let ids = (0..prop.size())
+                        .map(|i| {
+                            let text = prop.node(i).expect("pre-order index").to_string();
+                            let next = by_node.len();
+                            *by_node.entry(text).or_insert(next)
+                        })
+                        .collect();
+                    node_ids.push(ids);
+                    let mon = match reuse.remove(&key) {
+                        Some(mon) => mon,
+                        None => PropertyMonitor::new(ctx, prop, horizon)?.with_missing(missing),
+                    };
+                    monitors.push(mon);
+                    by_prop.insert(key, monitors.len() - 1);
+                    monitors.len() - 1
+                }
+            };
+            slots.push(slot);
+        }
+        let horizon = monitors
+            .iter()
+            .filter_map(|m| match m.horizon() {
+                Horizon::Samples(n) => Some(n),
+                Horizon::Span(_) => None,
+            })
+            .max()
+            .unwrap_or(0);
+        Ok(MonitorSet {
+            window: VecDeque::with_capacity(horizon + 1),
+            stamps: VecDeque::with_capacity(horizon + 1),
+            horizon,
+            solve_us: vec![0; monitors.len()],
+            monitors,
+            node_ids,
+            slots,
+            shared_nodes: by_node.len(),
+        })
+    }
+
+    /// Number of properties.
+    pub fn len(&self) -> usize {
+        self.slots.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.slots.is_empty()
+    }
+
+    /// Number of distinct monitors after merging identical properties.
+    pub fn distinct(&self) -> usize {
+        self.monitors.len()
+    }
+
+    /// Number of distinct sub-formulas across the pack.
+    pub fn shared_nodes(&self) -> usize {
+        self.shared_nodes
+    }
+
+    /// Longest sample-count horizon.  Span horizons may keep more samples.
+    pub fn horizon(&self) -> usize {
+        self.horizon
+    }
+
+    /// Current window, newest-first.
+    pub fn window(&self) -> &[Sample] {
+        self.window.as_slices().0
+    }
+
+    /// Timestamps (ns) of `window`, newest-first.
+    pub fn timestamps(&self) -> &[i64] {
+        self.stamps.as_slices().0
+    }
+
+    fn push(&mut self, ts_ns: i64, sample: Sample) {
+        self.window.push_front(sample);
+        self.stamps.push_front(ts_ns);
+        self.evict();
+    }
+
+    /// Slot a sample that arrived out of order into the window at its time
+    /// position (ahead of samples with the same timestamp).  Monitors pick
+    /// it up from their next tick, or at once via `retick`/`reeval`; a
+    /// sample older than every horizon is evicted straight away.
+    pub fn insert(&mut self, ts_ns: i64, sample: Sample) {
+        let at = self.stamps.iter().position(|&t| t <= ts_ns).unwrap_or(self.stamps.len());
+        self.window.insert(at, sample);
+        self.stamps.insert(at, ts_ns);
+        self.evict();
+    }
+
+    /// Copy of the window and timestamps with `sample` slotted in as by
+    /// `insert`, leaving the set untouched.
+    pub fn with_sample(&self, ts_ns: i64, sample: Sample) -> (Vec<Sample>, Vec<i64>) {
+        let at = self.stamps.iter().position(|&t| t <= ts_ns).unwrap_or(self.stamps.len());
+        let (mut window, mut stamps): (Vec<Sample>, Vec<i64>) =
+            (self.window.iter().cloned().collect(), self.stamps.iter().copied().collect());
+        window.insert(at, sample);
+        stamps.insert(at, ts_ns);
+        (window, stamps)
+    }
+
+    /// Evaluate every property with `eval_truth` on its horizon of a
+    /// newest-first window, one verdict per property.  Each window is judged
+    /// on its own; earlier ticks are not held (see `rejudge`).
+    pub fn judge(&self, window: &[Sample], stamps: &[i64]) -> Vec<Truth> {
+        let verdicts: Vec<Truth> = self
+            .monitors
+            .iter()
+            .map(|mon| {
+                let n = mon.horizon().window_len(stamps);
+                mon.judge(&window[..n], &stamps[..n])
+            })
+            .collect();
+        self.slots.iter().map(|&s| verdicts[s]).collect()
+    }
+
+    /// Verdicts as `tick` and `eval` report them, had the newest tick seen
+    /// `window` instead: its own verdict is re-judged, the earlier ticks in
+    /// each clause window still count.  The set is left untouched.
+    pub fn rejudge(&self, window: &[Sample], stamps: &[i64]) -> Vec<Truth> {
+        let verdicts: Vec<Truth> = self
+            .monitors
+            .iter()
+            .map(|mon| {
+                let n = mon.horizon().window_len(stamps);
+                mon.rejudged(&window[..n], &stamps[..n])
+            })
+            .collect();
+        self.slots.iter().map(|&s| verdicts[s]).collect()
+    }
+
+    /// Drop the samples no property can see any more.
+    fn evict(&mut self) {
+        let stamps = self.stamps.make_contiguous();
+        let keep = self.monitors.iter().map(|m| m.horizon().window_len(stamps)).max().unwrap_or(0);
+        self.window.truncate(keep);
+        self.stamps.truncate(keep);
+        self.window.make_contiguous();
+    }
+
+    /// Monitor of property `i` (shared with identical properties).
+    pub fn monitor(&self, i: usize) -> &PropertyMonitor<'ctx> {
+        &self.monitors[self.slots[i]]
+    }
+
+    /// Wall time of property `i`'s last solve in µs.
+    pub fn solve_us(&self, i: usize) -> u64 {
+        self.solve_us[self.slots[i]]
+    }
+
+    /// Per property, its monitor's `PropertyMonitor::history`.
+    pub fn histories(&self) -> Vec<Vec<Truth>> {
+        self.slots.iter().map(|&s| self.monitors[s].history()).collect()
+    }
+
+    /// Take up a saved state on a fresh set: `window` (oldest first) fills
+    /// the window without ticks, and each monitor resumes the history given
+    /// for the first of its properties that has one (`None`: starts afresh).
+    pub fn resume(
+        &mut self,
+        window: impl IntoIterator<Item = (i64, Sample)>,
+        histories: &[Option<Vec<Truth>>],
+        solver: bool,
+    ) {
+        for (ts_ns, sample) in window {
+            self.push(ts_ns, sample);
+        }
+        let mut resumed = vec![false; self.monitors.len()];
+        for (&slot, history) in self.slots.iter().zip(histories) {
+            if let Some(history) = history.as_deref().filter(|_| !resumed[slot]) {
+                self.monitors[slot].resume(history, solver);
+                resumed[slot] = true;
+            }
+        }
+    }
+
+    /// Push the newest sample, taken at `ts_ns`, and return one verdict per
+    /// property.
+    pub fn tick(&mut self, ts_ns: i64, sample: Sample) -> Vec<Truth> {
+        self.push(ts_ns, sample);
+        self.solve()
+    }
+
+    /// Re-judge the newest tick through the solver on the window as it is
+    /// now (after `insert`), replacing the clause batch it added.
+    pub fn retick(&mut self) -> Vec<Truth> {
+        self.monitors.iter_mut().for_each(PropertyMonitor::undo_tick);
+        self.solve()
+    }
+
+    /// `retick` for `eval`.
+    pub fn reeval(&mut self) -> Vec<Truth> {
+        self.monitors.iter_mut().for_each(PropertyMonitor::undo_tick);
+        self.evaluate()
+    }
+
+    fn solve(&mut self) -> Vec<Truth> {
+        let window = self.window.as_slices().0;
+        let stamps = self.stamps.as_slices().0;
+
+        // Suffixes of the window are identified by their start and length;
+        // how a reading is looked up depends on the policy.
+        let mut memo: HashMap<(usize, usize, usize, bool), Truth> = HashMap::new();
+        let verdicts: Vec<Truth> = self
+            .monitors
+            .iter_mut()
+            .zip(&self.node_ids)
+            .zip(&mut self.solve_us)
+            .map(|((mon, ids), us)| {
+                let n = mon.horizon().window_len(stamps);
+                let missing = mon.missing();
+                let hold = missing == Missing::HoldLast;
+                let started = Instant::now();
+                let holds = mon.tick_with(&window[..n], &stamps[..n], &mut |node, p, suffix, ts| {
+                    *memo
+                        .entry((ids[node], suffix.as_ptr() as usize, suffix.len(), hold))
+                        .or_insert_with(|| eval_truth(p, suffix, ts, missing))
+                });
+                *us = started.elapsed().as_micros() as u64;
+                holds
+            })
+            .collect();
+        self.slots.iter().map(|&s| verdicts[s]).collect()
+    }
+
+    /// Like `tick`, but evaluates each property directly with `eval_truth`
+    /// on its window, bypassing the solver.  A verdict is held over the same
+    /// ticks as the solver's clause window, so both flip together.
+    pub fn eval(&mut self, ts_ns: i64, sample: Sample) -> Vec<Truth> {
+        self.push(ts_ns, sample);
+        self.evaluate()
+    }
+
+    fn evaluate(&mut self) -> Vec<Truth> {
+        let window = self.window.as_slices().0;
+        let stamps = self.stamps.as_slices().0;
+        let verdicts: Vec<Truth> = self
+            .monitors
+            .iter_mut()
+            .map(|mon| {
+                let n = mon.horizon().window_len(stamps);
+                let missing = mon.missing();
+                mon.eval_with(&window[..n], &stamps[..n], &mut |_, p, w, ts| eval_truth(p, w, ts, missing))
+            })
+            .collect();
+        self.slots.iter().map(|&s| verdicts[s]).collect()
+    }
+}
+
+// ---------------------------
+// Unit tests
+// ---------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dsl::{Prop, Var};
+    use crate::monitor::solver_context;
+    use crate::parser::parse_prop;
+
+    fn pack(srcs: &[(&str, usize)]) -> Vec<(Prop, usize)> {
+        srcs.iter().map(|(s, h)| (parse_prop(s).unwrap(), *h)).collect()
+    }
+
+    #[test]
+    fn merges_identical_properties_and_shared_nodes() {
+        let ctx = solver_context();
+        let props = pack(&[
+            ("P <= 120", 6),
+            ("P <= 120 && T <= 80", 6),
+            ("P <= 120", 6),
+            ("P <= 120", 3),
+        ]);
+        let set = MonitorSet::new(&ctx, props).unwrap();
+        assert_eq!(set.len(), 4);
+        assert_eq!(set.distinct(), 3);
+        // `P <= 120`, `T <= 80` and the conjunction
+        assert_eq!(set.shared_nodes(), 3);
+        assert_eq!(set.horizon(), 6);
+    }
+
+    #[test]
+    fn verdicts_match_individual_monitors() {
+        let srcs = [
+            ("P <= 120", 6),
+            ("always[2](|dP| <= 5) && T <= 80", 6),
+            ("always[2](|dP| <= 5)", 3),
+            ("Valve == 0 ->[2] Flow <= 0.5", 4),
+            ("P <= 120", 6),
+        ];
+        let ctx = solver_context();
+        let mut set = MonitorSet::new(&ctx, pack(&srcs)).unwrap();
+        let mut solo: Vec<PropertyMonitor> =
+            pack(&srcs).into_iter().map(|(p, h)| PropertyMonitor::new(&ctx, p, h).unwrap()).collect();
+        // One sample per second, so timed and untimed `|dP|` agree.
+        const SEC: i64 = 1_000_000_000;
+        let (mut window, mut stamps): (Vec<Sample>, Vec<i64>) = (Vec::new(), Vec::new());
+        let readings = [(100.0, 1.0, 0.2), (104.0, 1.0, 0.9), (111.0, 0.0, 0.9), (125.0, 0.0, 0.3), (124.0, 0.0, 0.1), (100.0, 1.0, 2.0)];
+        for (t, (p, valve, flow)) in readings.into_iter().enumerate() {
+            let sample = HashMap::from([(Var::P, p), (Var::T, 20.0), (Var::Valve, valve), (Var::Flow, flow)]);
+            window.insert(0, sample.clone());
+            stamps.insert(0, t as i64 * SEC);
+            let got = set.tick(t as i64 * SEC, sample);
+            let want: Vec<Truth> = solo
+                .iter_mut()
+                .map(|m| {
+                    let h = m.horizon().window_len(&stamps);
+                    m.tick(&window[..h])
+                })
+                .collect();
+            assert_eq!(got, want);
+        }
+        assert_eq!(set.window().len(), 6);
+        assert_eq!(set.timestamps(), [5, 4, 3, 2, 1, 0].map(|t| t * SEC));
+    }
+
+    #[test]
+    fn reload_keeps_state_of_unchanged_properties() {
+        let ctx = solver_context();
+        let mut set = MonitorSet::new(&ctx, pack(&[("P <= 120", 3), ("T <= 80", 3)])).unwrap();
+        assert_eq!(set.tick(0, HashMap::from([(Var::P, 130.0), (Var::T, 20.0)])), [false, true].map(Truth::from));
+        set.reload(&ctx, pack(&[("T <= 80", 3), ("P <= 120", 3), ("Flow <= 1", 3)])).unwrap();
+        // The violation still sits in the clause window of `P <= 120`.
+        let low = HashMap::from([(Var::P, 100.0), (Var::T, 20.0), (Var::Flow, 0.0)]);
+        assert_eq!(set.tick(1, low.clone()), [true, false, true].map(Truth::from));
+        // `eval` holds it just as long
+        assert_eq!(set.eval(2, low.clone()), [true, false, true].map(Truth::from));
+        assert_eq!(set.eval(3, low), [true, true, true].map(Truth::from));
+        assert_eq!(set.timestamps(), [3, 2, 1]);
+    }
+
+    #[test]
+    fn span_horizons_evict_by_timestamp() {
+        const SEC: i64 = 1_000_000_000;
+        let ctx = solver_context();
+        let props = vec![
+            (parse_prop("P <= 120").unwrap(), Horizon::Samples(2)),
+            (parse_prop("always[9](|dP| <= 1)").unwrap(), Horizon::Span(10 * SEC)),
+        ];
+        let mut set = MonitorSet::new(&ctx, props).unwrap();
+        let sample = |p| HashMap::from([(Var::P, p)]);
+        // 100 ms cadence: the 10 s span keeps far more than 2 samples
+        for i in 0..=150 {
+            set.tick(i * SEC / 10, sample(100.0 + i as f64 * 0.05));
+        }
+        assert_eq!(set.window().len(), 101);
+        assert_eq!(*set.timestamps().last().unwrap(), 5 * SEC);
+        // +3 in 2 s is 1.5/s on the span, a legal step for the count horizon
+        assert_eq!(set.tick(17 * SEC, sample(110.5)), [true, false].map(Truth::from));
+        assert_eq!(set.eval(18 * SEC, sample(111.0)), [true, false].map(Truth::from));
+        // a long gap leaves only the newest sample in the span
+        set.tick(60 * SEC, sample(150.0));
+        assert_eq!(set.window().len(), 2);
+    }
+
+    #[test]
+    fn late_samples_slot_in_by_timestamp() {
+        let ctx = solver_context();
+        let mut set = MonitorSet::new(&ctx, pack(&[("P <= 120", 3), ("always[2](P <= 120)", 3)])).unwrap();
+        let sample = |p| HashMap::from([(Var::P, p)]);
+        for (ts, p) in [(10, 100.0), (30, 100.0), (40, 100.0)] {
+            set.eval(ts, sample(p));
+        }
+        let (window, stamps) = set.with_sample(20, sample(130.0));
+        assert_eq!(stamps, [40, 30, 20, 10]);
+        assert_eq!(set.judge(&window, &stamps), [true, false].map(Truth::from));
+        assert_eq!(set.timestamps(), [40, 30, 10]);
+
+        set.insert(20, sample(130.0));
+        assert_eq!(set.timestamps(), [40, 30, 20]);
+        assert_eq!(set.judge(set.window(), set.timestamps()), [true, false].map(Truth::from));
+        // older than every horizon: evicted on arrival
+        set.insert(5, sample(130.0));
+        assert_eq!(set.timestamps(), [40, 30, 20]);
+    }
+}
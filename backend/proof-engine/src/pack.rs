@@ -0,0 +1,431 @@
+//! Property packs: the monitored rule set, loaded from a TOML file.
+//!
+//! ```toml
+//! version = "2025.07-1"
+//!
+//! [assets]                          # asset id -> asset class
+//! "seg-7"     = "segment"
+//! "station-2" = "compressor"
+//!
+//! [[property]]
+//! id          = "seg7.max_pressure"
+//! description = "Line pressure stays under the MAOP"
+//! severity    = "critical"          # info | warning | critical
+//! asset_class = "segment"           # optional, defaults to every asset
+//! horizon     = 6                   # samples, or a duration: "30s"
+//!                                   # optional, defaults to WINDOW_HORIZON
+//! missing     = "violation"         # hold_last | violation | unknown
+//!                                   # optional, defaults to unknown
+//! alarm_margin = 5.0                # optional, see below
+//! expr        = "always[5](P <= 120)"
+//! ```
+//!
+//! A count horizon must cover the samples the expression reads.  A duration
+//! cannot be checked up front, so at runtime a span holding fewer samples
+//! than that is INCONCLUSIVE (subject to `missing`), never a vacuous PASS.
+//!
+//! `missing` says what a sample without one of the tags the expression
+//! reads means: hold the tag's last value, count it as a violation, or
+//! leave the verdict INCONCLUSIVE (see `dsl::Missing`).
+//!
+//! `alarm_margin` is an early warning: while the property passes, a packet
+//! is also published whenever its robustness margin (`dsl::robustness`)
+//! drops below this value, and again once it recovers.
+//!
+//! Every asset (PLC, station, pipeline segment) is monitored on its own
+//! trace.  A property with an `asset_class` runs only on the assets the
+//! `[assets]` table puts in that class; one without runs on all of them,
+//! listed or not.
+//!
+//! Ids are the stable `property_id` carried by every proof packet, so they
+//! must be unique within a pack and should never be reused for a different
+//! rule.
+
+use crate::dsl::{Missing, Prop};
+use crate::monitor::Horizon;
+use crate::parser::{parse_prop, ParseError};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::path::Path;
+use thiserror::Error;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Info,
+    Warning,
+    Critical,
+}
+
+#[derive(Error, Debug)]
+pub enum PackError {
+    #[error("cannot read property pack {path}: {source}")]
+    Io { path: String, source: std::io::Error },
+    #[error("malformed property pack: {0}")]
+    Toml(#[from] toml::de::Error),
+    #[error("pack version must not be empty")]
+    NoVersion,
+    #[error("pack declares no properties")]
+    Empty,
+    #[error("invalid property id {0:?} (use letters, digits, `_ . -`)")]
+    InvalidId(String),
+    #[error("duplicate property id {0:?}")]
+    DuplicateId(String),
+    #[error("property {id}: {}", source.render(expr))]
+    Expr { id: String, expr: String, source: ParseError },
+    #[error("property {id}: horizon {horizon} is shorter than the {needed} samples the expression reads")]
+    Horizon { id: String, horizon: usize, needed: usize },
+    #[error("property {id}: {reason}")]
+    InvalidHorizon { id: String, reason: String },
+    #[error("property {id}: alarm_margin must be a positive number, not {margin}")]
+    AlarmMargin { id: String, margin: f64 },
+    #[error("property {id}: no asset is in class {class:?}")]
+    UnknownAssetClass { id: String, class: String },
+    #[error("pack content changed but its version {0:?} did not; bump `version`")]
+    VersionUnchanged(String),
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PackFile {
+    version: String,
+    #[serde(default)]
+    assets: BTreeMap<String, String>,
+    #[serde(rename = "property", default)]
+    properties: Vec<PropertySpec>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PropertySpec {
+    id: String,
+    #[serde(default)]
+    description: String,
+    severity: Severity,
+    horizon: Option<HorizonSpec>,
+    #[serde(default)]
+    missing: Missing,
+    alarm_margin: Option<f64>,
+    asset_class: Option<String>,
+    expr: String,
+}
+
+/// `horizon = 6` or `horizon = "30s"`.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum HorizonSpec {
+    Samples(usize),
+    Text(String),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Property {
+    pub id: String,
+    pub description: String,
+    pub severity: Severity,
+    pub horizon: Horizon,
+    pub missing: Missing,
+    /// Robustness under which a PASS raises a margin alarm.
+    pub alarm_margin: Option<f64>,
+    /// Assets this property runs on; `None` for all of them.
+    pub asset_class: Option<String>,
+    /// Source text as written in the pack.
+    pub expr: String,
+    pub prop: Prop,
+}
+
+impl Property {
+    /// Same verdicts on the same traces: monitoring state can carry over.
+    pub fn same_rule(&self, other: &Property) -> bool {
+        self.prop == other.prop
+            && self.horizon == other.horizon
+            && self.missing == other.missing
+            && self.asset_class == other.asset_class
+    }
+}
+
+/// Changes between two pack versions, by property id.
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct PackDiff {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub changed: Vec<String>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct PropertyPack {
+    pub version: String,
+    /// Asset id → asset class.
+    pub assets: BTreeMap<String, String>,
+    pub properties: Vec<Property>,
+}
+
+impl PropertyPack {
+    /// Parse and validate a pack; properties without a `horizon` get
+    /// `default_horizon`.
+    pub fn from_str(src: &str, default_horizon: impl Into<Horizon>) -> Result<Self, PackError> {
+        let default_horizon = default_horizon.into();
+        let file: PackFile = toml::from_str(src)?;
+        if file.version.trim().is_empty() {
+            return Err(PackError::NoVersion);
+        }
+        if file.properties.is_empty() {
+            return Err(PackError::Empty);
+        }
+        let mut seen = HashSet::new();
+        let mut properties = Vec::with_capacity(file.properties.len());
+        for spec in file.properties {
+            if !valid_id(&spec.id) {
+                return Err(PackError::InvalidId(spec.id));
+            }
+            if !seen.insert(spec.id.clone()) {
+                return Err(PackError::DuplicateId(spec.id));
+            }
+            let prop = match parse_prop(&spec.expr) {
+                Ok(p) => p,
+                Err(source) => return Err(PackError::Expr { id: spec.id, expr: spec.expr, source }),
+            };
+            let horizon = match spec.horizon {
+                None => default_horizon,
+                Some(HorizonSpec::Samples(n)) => Horizon::Samples(n),
+                Some(HorizonSpec::Text(text)) => match text.parse() {
+                    Ok(h) => h,
+                    Err(reason) => return Err(PackError::InvalidHorizon { id: spec.id, reason }),
+                },
+            };
+            if let Err(e) = horizon.validate() {
+                return Err(PackError::InvalidHorizon { id: spec.id, reason: e.to_string() });
+            }
+            if let Some(margin) = spec.alarm_margin.filter(|m| !(m.is_finite() && *m > 0.0)) {
+                return Err(PackError::AlarmMargin { id: spec.id, margin });
+            }
+            if let Some(class) = &spec.asset_class {
+                if !file.assets.values().any(|c| c == class) {
+                    return Err(PackError::UnknownAssetClass { id: spec.id, class: class.clone() });
+                }
+            }
+            // Spans are checked per tick (`PropertyMonitor`)
+            let needed = prop.history();
+            if let Horizon::Samples(n) = horizon {
+                if n < needed {
+                    return Err(PackError::Horizon { id: spec.id, horizon: n, needed });
+                }
+            }
+            properties.push(Property {
+                id: spec.id,
+                description: spec.description,
+                severity: spec.severity,
+                horizon,
+                missing: spec.missing,
+                alarm_margin: spec.alarm_margin,
+                asset_class: spec.asset_class,
+                expr: spec.expr,
+                prop,
+            });
+        }
+        Ok(PropertyPack { version: file.version, assets: file.assets, properties })
+    }
+
+    pub fn load(path: impl AsRef<Path>, default_horizon: impl Into<Horizon>) -> Result<Self, PackError> {
+        let path = path.as_ref();
+        let src = std::fs::read_to_string(path)
+            .map_err(|source| PackError::Io { path: path.display().to_string(), source })?;
+        Self::from_str(&src, default_horizon)
+    }
+
+    /// Whether `self`, loaded while `current` runs, should replace it: not
+    /// if it is the same pack, and an error if it differs under the same
+    /// version, since packets identify their rules by pack version.
+    pub fn replaces(&self, current: &PropertyPack) -> Result<bool, PackError> {
+        if self == current {
+            Ok(false)
+        } else if self.version == current.version {
+            Err(PackError::VersionUnchanged(self.version.clone()))
+        } else {
+            Ok(true)
+        }
+    }
+
+    pub fn index_of(&self, id: &str) -> Option<usize> {
+        self.properties.iter().position(|p| p.id == id)
+    }
+
+    /// Property ids added, removed or given new semantics by `next`.
+    /// Edits to description or severity alone leave a property unchanged.
+    pub fn diff(&self, next: &PropertyPack) -> PackDiff {
+        let mut diff = PackDiff::default();
+        for p in &next.properties {
+            match self.index_of(&p.id).map(|i| &self.properties[i]) {
+                None => diff.added.push(p.id.clone()),
+                Some(old) if !old.same_rule(p) => diff.changed.push(p.id.clone()),
+                Some(_) => {}
+            }
+        }
+        for p in &self.properties {
+            if next.index_of(&p.id).is_none() {
+                diff.removed.push(p.id.clone());
+            }
+        }
+        diff
+    }
+
+    /// `(formula, horizon, missing)` in pack order, as `MonitorSet` takes them.
+    pub fn monitored(&self) -> impl Iterator<Item = (Prop, Horizon, Missing)> + '_ {
+        self.properties.iter().map(|p| (p.prop.clone(), p.horizon, p.missing))
+    }
+
+    /// `monitored` restricted to the properties at `indices`.
+    pub fn monitored_at<'a>(&'a self, indices: &'a [usize]) -> impl Iterator<Item = (Prop, Horizon, Missing)> + 'a {
+        indices.iter().map(|&i| &self.properties[i]).map(|p| (p.prop.clone(), p.horizon, p.missing))
+    }
+
+    /// Indices of the properties that run on `asset`, in pack order.
+    pub fn bound_to(&self, asset: &str) -> Vec<usize> {
+        let class = self.assets.get(asset);
+        self.properties
+            .iter()
+            .enumerate()
+            .filter(|(_, p)| p.asset_class.is_none() || p.asset_class.as_ref() == class)
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    /// Longest sample-count horizon in the pack.
+    pub fn max_horizon(&self) -> usize {
+        self.properties
+            .iter()
+            .filter_map(|p| match p.horizon {
+                Horizon::Samples(n) => Some(n),
+                Horizon::Span(_) => None,
+            })
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+fn valid_id(id: &str) -> bool {
+    !id.is_empty() && id.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '-'))
+}
+
+// ---------------------------
+// Unit tests
+// ---------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dsl::Var;
+
+    const PACK: &str = r#"
+        version = "t-1"
+
+        [assets]
+        seg7 = "segment"
+        seg8 = "segment"
+        cs2 = "compressor"
+
+        [[property]]
+        id = "seg7.max_pressure"
+        description = "MAOP"
+        severity = "critical"
+        expr = "P <= 120"
+
+        [[property]]
+        id = "seg7.ramp"
+        severity = "warning"
+        horizon = 8
+        missing = "hold_last"
+        asset_class = "segment"
+        expr = "always[5](|dP| <= 5)"
+    "#;
+
+    #[test]
+    fn loads_pack() {
+        let pack = PropertyPack::from_str(PACK, 6).unwrap();
+        assert_eq!(pack.version, "t-1");
+        assert_eq!(pack.properties[0].prop, Prop::Le(Var::P, 120.0));
+        assert_eq!(pack.properties[0].horizon, Horizon::Samples(6));
+        assert_eq!(pack.properties[1].severity, Severity::Warning);
+        assert_eq!(pack.properties[0].missing, Missing::Unknown);
+        assert_eq!(pack.properties[1].missing, Missing::HoldLast);
+        assert_eq!(pack.max_horizon(), 8);
+    }
+
+    #[test]
+    fn shipped_default_pack_is_valid() {
+        let pack = PropertyPack::from_str(include_str!("../packs/default.toml"), 6).unwrap();
+        assert!(!pack.properties.is_empty());
+    }
+
+    #[test]
+    fn rejects_invalid_packs() {
+        let dup = PACK.replace("seg7.ramp", "seg7.max_pressure");
+        assert!(matches!(PropertyPack::from_str(&dup, 6), Err(PackError::DuplicateId(_))));
+
+        let short = PACK.replace("horizon = 8", "horizon = 6");
+        let err = PropertyPack::from_str(&short, 6).unwrap_err();
+        assert!(matches!(err, PackError::Horizon { horizon: 6, needed: 7, .. }), "{err}");
+
+        let bad = PACK.replace("P <= 120", "P <= ");
+        let err = PropertyPack::from_str(&bad, 6).unwrap_err();
+        assert!(matches!(&err, PackError::Expr { id, .. } if id == "seg7.max_pressure"));
+        assert!(err.to_string().contains("expected a number"), "{err}");
+
+        let typo = PACK.replace("severity = \"warning\"", "severity = \"warn\"");
+        assert!(matches!(PropertyPack::from_str(&typo, 6), Err(PackError::Toml(_))));
+        let zero = PACK.replace("\"hold_last\"", "\"zero\"");
+        assert!(matches!(PropertyPack::from_str(&zero, 6), Err(PackError::Toml(_))));
+
+        let empty = PACK.replace("horizon = 8", "horizon = 0");
+        assert!(matches!(PropertyPack::from_str(&empty, 6), Err(PackError::InvalidHorizon { .. })));
+        let span = PACK.replace("horizon = 8", "horizon = \"30 fortnights\"");
+        assert!(matches!(PropertyPack::from_str(&span, 6), Err(PackError::InvalidHorizon { .. })));
+
+        let margin = PACK.replace("missing = \"hold_last\"", "alarm_margin = -1.0");
+        assert!(matches!(PropertyPack::from_str(&margin, 6), Err(PackError::AlarmMargin { .. })));
+
+        let class = PACK.replace("asset_class = \"segment\"", "asset_class = \"segmnet\"");
+        assert!(matches!(PropertyPack::from_str(&class, 6), Err(PackError::UnknownAssetClass { .. })));
+    }
+
+    #[test]
+    fn binds_properties_to_asset_classes() {
+        let pack = PropertyPack::from_str(PACK, 6).unwrap();
+        assert_eq!(pack.bound_to("seg8"), [0, 1]);
+        assert_eq!(pack.bound_to("cs2"), [0]);
+        assert_eq!(pack.bound_to("unlisted"), [0]);
+
+        let moved = PACK.replace("asset_class = \"segment\"", "asset_class = \"compressor\"");
+        let diff = pack.diff(&PropertyPack::from_str(&moved, 6).unwrap());
+        assert_eq!(diff.changed, ["seg7.ramp"]);
+    }
+
+    #[test]
+    fn duration_horizons() {
+        let src = PACK.replace("horizon = 8", "horizon = \"30s\"");
+        let pack = PropertyPack::from_str(&src, "2m".parse::<Horizon>().unwrap()).unwrap();
+        assert_eq!(pack.properties[0].horizon, Horizon::Span(120_000_000_000));
+        assert_eq!(pack.properties[1].horizon, Horizon::Span(30_000_000_000));
+        assert_eq!(pack.max_horizon(), 0);
+    }
+
+    #[test]
+    fn diff_tracks_rule_changes_by_id() {
+        let old = PropertyPack::from_str(PACK, 6).unwrap();
+        let src = PACK
+            .replace("description = \"MAOP\"", "description = \"MAOP, amended\"")
+            .replace("horizon = 8", "horizon = 9")
+            + "[[property]]\nid = \"seg7.temp\"\nseverity = \"info\"\nexpr = \"T <= 80\"\n";
+        let new = PropertyPack::from_str(&src, 6).unwrap();
+        let diff = old.diff(&new);
+        assert_eq!(diff.added, ["seg7.temp"]);
+        assert_eq!(diff.changed, ["seg7.ramp"]);
+        assert!(diff.removed.is_empty());
+        assert_eq!(new.diff(&old).removed, ["seg7.temp"]);
+
+        // a hot reload needs a new version for any edit
+        assert!(matches!(new.replaces(&old), Err(PackError::VersionUnchanged(_))));
+        assert!(!old.replaces(&old.clone()).unwrap());
+        let bumped = PropertyPack::from_str(&src.replace("\"t-1\"", "\"t-2\""), 6).unwrap();
+        assert!(bumped.replaces(&old).unwrap());
+    }
+}
@@ -0,0 +1,584 @@
+//! Text syntax for `dsl::Prop`, with a parser that reports source spans and
+//! a pretty-printer (`Display`) that round-trips through it.
+//!
+//! ```text
+//! prop    := or ( "->" prop | "->[" N "]" prop )?     -- Implies / ImplWithin, right-assoc
+//! or      := and ( "||" and )*
+//! and     := unary ( "&&" unary )*
+//! unary   := "!" unary | "always" "[" N "]" "(" prop ")" | atom
+//! atom    := "(" prop ")"
+//!          | "|d" TAG "|" "<=" NUM                    -- RateBound, per second
+//!          | TAG CMP NUM ( "+-" NUM )?               -- tolerance only for == / !=
+//! CMP     := "<=" | ">=" | "<" | ">" | "==" | "!="
+//! ```
+//!
+//! `always[k](p)` is Lean's `windowAll k p` (the newest `k + 1` windows) and
+//! `p ->[k] q` is `implWithin p q k`.  Example:
+//! `always[6](P <= 120 && |dP| <= 5)`.
+
+use crate::dsl::{Prop, Var};
+use crate::tags;
+use std::fmt;
+use std::ops::Range;
+use thiserror::Error;
+
+#[derive(Error, Debug, Clone, PartialEq)]
+#[error("{message} at {}..{}", span.start, span.end)]
+pub struct ParseError {
+    pub span: Range<usize>,
+    pub message: String,
+}
+
+impl ParseError {
+    fn new(span: Range<usize>, message: impl Into<String>) -> Self {
+        Self { span, message: message.into() }
+    }
+
+    /// Human-readable report: the offending line with a caret underline.
+    pub fn render(&self, src: &str) -> String {
+        let start = self.span.start.min(src.len());
+        let line_start = src[..start].rfind('\n').map_or(0, |i| i + 1);
+        let line_end = src[start..].find('\n').map_or(src.len(), |i| start + i);
+        let line_no = src[..start].matches('\n').count() + 1;
+        let width = self.span.end.clamp(start + 1, line_end.max(start + 1)) - start;
+        format!(
+            "error: {}\n{:>4} | {}\n     | {}{}",
+            self.message,
+            line_no,
+            &src[line_start..line_end],
+            " ".repeat(src[line_start..start].chars().count()),
+            "^".repeat(width),
+        )
+    }
+}
+
+/// Parse one property expression, resolving tags through the process-wide
+/// registry (a closed registry rejects unknown tags).
+pub fn parse_prop(src: &str) -> Result<Prop, ParseError> {
+    parse_prop_with(src, &mut tags::lookup_or_intern)
+}
+
+/// Parse with a caller-supplied tag resolver.
+pub fn parse_prop_with(src: &str, resolve: &mut dyn FnMut(&str) -> Option<Var>) -> Result<Prop, ParseError> {
+    let tokens = lex(src)?;
+    let mut p = Parser { src, tokens, pos: 0, resolve };
+    let prop = p.prop()?;
+    match p.peek() {
+        Tok::Eof => Ok(prop),
+        _ => Err(p.unexpected("end of input")),
+    }
+}
+
+// ---------------------------
+// Lexer
+// ---------------------------
+
+#[derive(Clone, Debug, PartialEq)]
+enum Tok {
+    Ident(String),
+    Num(f64),
+    Cmp(Cmp),
+    AndAnd,
+    OrOr,
+    Bar,
+    Bang,
+    Arrow,
+    PlusMinus,
+    Minus,
+    LParen,
+    RParen,
+    LBracket,
+    RBracket,
+    Eof,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Cmp {
+    Le,
+    Ge,
+    Lt,
+    Gt,
+    Eq,
+    Ne,
+}
+
+struct Token {
+    tok: Tok,
+    span: Range<usize>,
+}
+
+fn is_ident_start(c: char) -> bool {
+    c.is_ascii_alphabetic() || c == '_'
+}
+
+fn lex(src: &str) -> Result<Vec<Token>, ParseError> {
+    let bytes = src.as_bytes();
+    let mut out = Vec::new();
+    let mut i = 0;
+    while let Some(c) = src[i..].chars().next() {
+        if c.is_whitespace() {
+            i += c.len_utf8();
+            continue;
+        }
+        let start = i;
+        let two = src.get(i..i + 2).unwrap_or("");
+        let tok = match two {
+            "<=" => Some(Tok::Cmp(Cmp::Le)),
+            ">=" => Some(Tok::Cmp(Cmp::Ge)),
+            "==" => Some(Tok::Cmp(Cmp::Eq)),
+            "!=" => Some(Tok::Cmp(Cmp::Ne)),
+            "&&" => Some(Tok::AndAnd),
+            "||" => Some(Tok::OrOr),
+            "->" => Some(Tok::Arrow),
+            "+-" => Some(Tok::PlusMinus),
+            _ => None,
+        };
+        if let Some(tok) = tok {
+            i += 2;
+            out.push(Token { tok, span: start..i });
+            continue;
+        }
+        let tok = match c {
+            '<' => Tok::Cmp(Cmp::Lt),
+            '>' => Tok::Cmp(Cmp::Gt),
+            '|' => Tok::Bar,
+            '!' => Tok::Bang,
+            '-' => Tok::Minus,
+            '(' => Tok::LParen,
+            ')' => Tok::RParen,
+            '[' => Tok::LBracket,
+            ']' => Tok::RBracket,
+            '±' => {
+                i += '±'.len_utf8();
+                out.push(Token { tok: Tok::PlusMinus, span: start..i });
+                continue;
+            }
+            c if c.is_ascii_digit() || c == '.' => {
+                i += 1;
+                while i < bytes.len() {
+                    let d = bytes[i] as char;
+                    let exp_sign = (d == '-' || d == '+') && matches!(bytes[i - 1], b'e' | b'E');
+                    if d.is_ascii_digit() || d == '.' || d == 'e' || d == 'E' || exp_sign {
+                        i += 1;
+                    } else {
+                        break;
+                    }
+                }
+                let text = &src[start..i];
+                let n = text
+                    .parse::<f64>()
+                    .map_err(|_| ParseError::new(start..i, format!("invalid number {text:?}")))?;
+                // `inf` would print as a tag name
+                if !n.is_finite() {
+                    return Err(ParseError::new(start..i, format!("number {text:?} is out of range")));
+                }
+                out.push(Token { tok: Tok::Num(n), span: start..i });
+                continue;
+            }
+            c if is_ident_start(c) => {
+                i += 1;
+                while i < bytes.len() {
+                    let d = bytes[i] as char;
+                    // `-` belongs to a tag name unless it starts `->`
+                    let dash = d == '-' && bytes.get(i + 1).is_some_and(|&n| (n as char).is_ascii_alphanumeric());
+                    if d.is_ascii_alphanumeric() || matches!(d, '_' | '.' | ':') || dash {
+                        i += 1;
+                    } else {
+                        break;
+                    }
+                }
+                // A trailing `-` would not survive printing (`Seg- <= 1`)
+                if bytes.get(i) == Some(&b'-') && bytes.get(i + 1) != Some(&b'>') {
+                    return Err(ParseError::new(start..i + 1, "tag names cannot end in `-`"));
+                }
+                out.push(Token { tok: Tok::Ident(src[start..i].to_string()), span: start..i });
+                continue;
+            }
+            _ => {
+                let end = start + c.len_utf8();
+                return Err(ParseError::new(start..end, format!("unexpected character {c:?}")));
+            }
+        };
+        i += 1;
+        out.push(Token { tok, span: start..i });
+    }
+    out.push(Token { tok: Tok::Eof, span: src.len()..src.len() });
+    Ok(out)
+}
+
+// ---------------------------
+// Parser
+// ---------------------------
+
+struct Parser<'a> {
+    src: &'a str,
+    tokens: Vec<Token>,
+    pos: usize,
+    resolve: &'a mut dyn FnMut(&str) -> Option<Var>,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> &Tok {
+        &self.tokens[self.pos].tok
+    }
+
+    fn span(&self) -> Range<usize> {
+        self.tokens[self.pos].span.clone()
+    }
+
+    fn bump(&mut self) -> Token {
+        let t = Token { tok: self.tokens[self.pos].tok.clone(), span: self.span() };
+        if self.pos + 1 < self.tokens.len() {
+            self.pos += 1;
+        }
+        t
+    }
+
+    fn eat(&mut self, tok: &Tok) -> bool {
+        if self.peek() == tok {
+            self.bump();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn unexpected(&self, expected: &str) -> ParseError {
+        let span = self.span();
+        let found = if span.is_empty() { "end of input".to_string() } else { format!("{:?}", &self.src[span.clone()]) };
+        ParseError::new(span, format!("expected {expected}, found {found}"))
+    }
+
+    fn expect(&mut self, tok: &Tok, what: &str) -> Result<(), ParseError> {
+        if self.eat(tok) { Ok(()) } else { Err(self.unexpected(what)) }
+    }
+
+    fn prop(&mut self) -> Result<Prop, ParseError> {
+        let lhs = self.or()?;
+        if !self.eat(&Tok::Arrow) {
+            return Ok(lhs);
+        }
+        if self.eat(&Tok::LBracket) {
+            let k = self.count()?;
+            self.expect(&Tok::RBracket, "`]`")?;
+            let rhs = self.prop()?;
+            return Ok(Prop::ImplWithin(Box::new(lhs), Box::new(rhs), k));
+        }
+        let rhs = self.prop()?;
+        Ok(Prop::Implies(Box::new(lhs), Box::new(rhs)))
+    }
+
+    fn or(&mut self) -> Result<Prop, ParseError> {
+        let mut lhs = self.and()?;
+        while self.eat(&Tok::OrOr) {
+            let rhs = self.and()?;
+            lhs = Prop::Or(Box::new(lhs), Box::new(rhs));
+        }
+        Ok(lhs)
+    }
+
+    fn and(&mut self) -> Result<Prop, ParseError> {
+        let mut lhs = self.unary()?;
+        while self.eat(&Tok::AndAnd) {
+            let rhs = self.unary()?;
+            lhs = Prop::And(Box::new(lhs), Box::new(rhs));
+        }
+        Ok(lhs)
+    }
+
+    fn unary(&mut self) -> Result<Prop, ParseError> {
+        if self.eat(&Tok::Bang) {
+            return Ok(Prop::Not(Box::new(self.unary()?)));
+        }
+        let always = matches!(self.peek(), Tok::Ident(w) if w == "always")
+            && matches!(self.tokens.get(self.pos + 1).map(|t| &t.tok), Some(Tok::LBracket));
+        if always {
+            self.bump();
+            self.bump();
+            let k = self.count()?;
+            self.expect(&Tok::RBracket, "`]`")?;
+            self.expect(&Tok::LParen, "`(`")?;
+            let body = self.prop()?;
+            self.expect(&Tok::RParen, "`)`")?;
+            return Ok(Prop::WindowAll(k, Box::new(body)));
+        }
+        self.atom()
+    }
+
+    fn atom(&mut self) -> Result<Prop, ParseError> {
+        if self.eat(&Tok::LParen) {
+            let p = self.prop()?;
+            self.expect(&Tok::RParen, "`)`")?;
+            return Ok(p);
+        }
+        if self.eat(&Tok::Bar) {
+            let span = self.span();
+            let var = match self.bump().tok {
+                Tok::Ident(name) if name.len() > 1 && name.starts_with('d') => {
+                    self.var(&name[1..], span.start + 1..span.end)?
+                }
+                _ => return Err(ParseError::new(span, "expected `d<tag>` inside `|…|`")),
+            };
+            self.expect(&Tok::Bar, "`|`")?;
+            self.expect(&Tok::Cmp(Cmp::Le), "`<=` (rates only have upper bounds)")?;
+            let k = self.number()?;
+            return Ok(Prop::RateBound(var, k));
+        }
+        let var = match self.peek() {
+            Tok::Ident(name) => {
+                let (name, span) = (name.clone(), self.span());
+                self.bump();
+                self.var(&name, span)?
+            }
+            _ => return Err(self.unexpected("a tag, `(`, `!`, `|d<tag>|` or `always[k](…)`")),
+        };
+        let cmp = match self.peek() {
+            Tok::Cmp(c) => {
+                let c = *c;
+                self.bump();
+                c
+            }
+            _ => return Err(self.unexpected("a comparison (<=, >=, <, >, ==, !=)")),
+        };
+        let k = self.number()?;
+        let tol_span = self.span();
+        let tol = if self.eat(&Tok::PlusMinus) {
+            let tol = self.number()?;
+            if !matches!(cmp, Cmp::Eq | Cmp::Ne) {
+                return Err(ParseError::new(tol_span.start..self.tokens[self.pos - 1].span.end,
+                                           "tolerance `+-` only applies to `==` and `!=`"));
+            }
+            if tol < 0.0 {
+                return Err(ParseError::new(tol_span, "tolerance must be non-negative"));
+            }
+            tol
+        } else {
+            0.0
+        };
+        Ok(match cmp {
+            Cmp::Le => Prop::Le(var, k),
+            Cmp::Ge => Prop::Ge(var, k),
+            Cmp::Lt => Prop::Lt(var, k),
+            Cmp::Gt => Prop::Gt(var, k),
+            Cmp::Eq => Prop::Eq(var, k, tol),
+            Cmp::Ne => Prop::Ne(var, k, tol),
+        })
+    }
+
+    fn var(&mut self, name: &str, span: Range<usize>) -> Result<Var, ParseError> {
+        (self.resolve)(name)
+            .ok_or_else(|| ParseError::new(span, format!("unknown tag {name:?}")))
+    }
+
+    fn number(&mut self) -> Result<f64, ParseError> {
+        let neg = self.eat(&Tok::Minus);
+        match self.peek() {
+            Tok::Num(n) => {
+                let n = *n;
+                self.bump();
+                Ok(if neg { -n } else { n })
+            }
+            _ => Err(self.unexpected("a number")),
+        }
+    }
+
+    fn count(&mut self) -> Result<usize, ParseError> {
+        let span = self.span();
+        match self.peek() {
+            Tok::Num(n) if n.fract() == 0.0 && *n >= 0.0 && *n <= u32::MAX as f64 => {
+                let n = *n as usize;
+                self.bump();
+                Ok(n)
+            }
+            Tok::Num(_) => Err(ParseError::new(span, "expected a sample count (non-negative integer)")),
+            _ => Err(self.unexpected("a sample count")),
+        }
+    }
+}
+
+// ---------------------------
+// Pretty-printer
+// ---------------------------
+
+/// Binding strength, loosest first.
+fn prec(p: &Prop) -> u8 {
+    use Prop::*;
+    match p {
+        Implies(..) | ImplWithin(..) => 1,
+        Or(..) => 2,
+        And(..) => 3,
+        Not(..) | WindowAll(..) => 4,
+        _ => 5,
+    }
+}
+
+fn write_prec(f: &mut fmt::Formatter<'_>, p: &Prop, min: u8) -> fmt::Result {
+    if prec(p) < min {
+        f.write_str("(")?;
+        write_prop(f, p)?;
+        f.write_str(")")
+    } else {
+        write_prop(f, p)
+    }
+}
+
+fn write_tol(f: &mut fmt::Formatter<'_>, tol: f64) -> fmt::Result {
+    if tol != 0.0 { write!(f, " +- {tol}") } else { Ok(()) }
+}
+
+fn write_prop(f: &mut fmt::Formatter<'_>, p: &Prop) -> fmt::Result {
+    use Prop::*;
+    match p {
+        Le(v, k) => write!(f, "{v} <= {k}"),
+        Ge(v, k) => write!(f, "{v} >= {k}"),
+        Lt(v, k) => write!(f, "{v} < {k}"),
+        Gt(v, k) => write!(f, "{v} > {k}"),
+        Eq(v, k, tol) => {
+            write!(f, "{v} == {k}")?;
+            write_tol(f, *tol)
+        }
+        Ne(v, k, tol) => {
+            write!(f, "{v} != {k}")?;
+            write_tol(f, *tol)
+        }
+        RateBound(v, k) => write!(f, "|d{v}| <= {k}"),
+        WindowAll(k, q) => {
+            write!(f, "always[{k}](")?;
+            write_prop(f, q)?;
+            f.write_str(")")
+        }
+        ImplWithin(a, b, k) => {
+            write_prec(f, a, 2)?;
+            write!(f, " ->[{k}] ")?;
+            write_prec(f, b, 1)
+        }
+        Implies(a, b) => {
+            write_prec(f, a, 2)?;
+            f.write_str(" -> ")?;
+            write_prec(f, b, 1)
+        }
+        Or(a, b) => {
+            write_prec(f, a, 2)?;
+            f.write_str(" || ")?;
+            write_prec(f, b, 3)
+        }
+        And(a, b) => {
+            write_prec(f, a, 3)?;
+            f.write_str(" && ")?;
+            write_prec(f, b, 4)
+        }
+        Not(q) => {
+            f.write_str("!")?;
+            write_prec(f, q, 4)
+        }
+    }
+}
+
+impl fmt::Display for Prop {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_prop(f, self)
+    }
+}
+
+// ---------------------------
+// Unit tests
+// ---------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn b(p: Prop) -> Box<Prop> {
+        Box::new(p)
+    }
+
+    #[test]
+    fn parses_example() {
+        let p = parse_prop("always[6](P <= 120 && |dP| <= 5)").unwrap();
+        assert_eq!(
+            p,
+            Prop::WindowAll(6, b(Prop::And(b(Prop::Le(Var::P, 120.0)), b(Prop::RateBound(Var::P, 5.0)))))
+        );
+    }
+
+    #[test]
+    fn precedence_and_associativity() {
+        let p = parse_prop("!P > 1 || T < 2 && Flow >= 3 -> Valve == 1").unwrap();
+        let lhs = Prop::Or(
+            b(Prop::Not(b(Prop::Gt(Var::P, 1.0)))),
+            b(Prop::And(b(Prop::Lt(Var::T, 2.0)), b(Prop::Ge(Var::Flow, 3.0)))),
+        );
+        assert_eq!(p, Prop::Implies(b(lhs), b(Prop::Eq(Var::Valve, 1.0, 0.0))));
+        let p = parse_prop("Valve == 0 ->[3] Flow <= 0.5 +- 0").unwrap_err();
+        assert!(p.message.contains("tolerance"), "{p}");
+        let p = parse_prop("Valve == 0 ->[3] Flow != -1.5e-1 +- 0.25").unwrap();
+        assert_eq!(p, Prop::ImplWithin(b(Prop::Eq(Var::Valve, 0.0, 0.0)), b(Prop::Ne(Var::Flow, -0.15, 0.25)), 3));
+    }
+
+    #[test]
+    fn round_trips() {
+        let srcs = [
+            "always[6](P <= 120 && |dP| <= 5)",
+            "P <= 1 || T <= 2 || Flow <= 3",
+            "P <= 1 || (T <= 2 || Flow <= 3)",
+            "(P <= 1 -> T <= 2) -> Flow <= 3",
+            "P <= 1 -> T <= 2 -> Flow <= 3",
+            "!(P < 1 && T > 2) && !!Valve != 0 +- 0.5",
+            "Valve == 1 ->[4] Flow <= 0.1 || P >= -3",
+            "(Valve == 1 ->[4] Flow <= 0.1) || P >= -3",
+            "always[0](!always[2](T <= 80))",
+            "(P <= 1 ->[2] T <= 2) ->[1] Flow <= 3",
+        ];
+        for src in srcs {
+            let p = parse_prop(src).unwrap();
+            let printed = p.to_string();
+            assert_eq!(printed, src, "printer is not canonical");
+            assert_eq!(parse_prop(&printed).unwrap(), p);
+        }
+    }
+
+    #[test]
+    fn rejects_tokens_the_printer_cannot_round_trip() {
+        let e = parse_prop("Seg- <= 1").unwrap_err();
+        assert_eq!(e.span, 0..4);
+        assert!(e.message.contains("cannot end in `-`"), "{e}");
+        assert!(parse_prop("|dSeg-| <= 1").is_err());
+        let e = parse_prop("P <= 1e999").unwrap_err();
+        assert_eq!(e.span, 5..10);
+        assert!(e.message.contains("out of range"), "{e}");
+
+        // inner dashes, `->` straight after a tag and the largest finite literal do
+        for src in ["Seg-7 <= 1.7976931348623157e308", "Valve-2 == 1->[2] Flow <= 0.1"] {
+            let p = parse_prop(src).unwrap();
+            assert_eq!(parse_prop(&p.to_string()).unwrap(), p, "{p}");
+        }
+    }
+
+    #[test]
+    fn errors_carry_spans() {
+        let src = "P <= 120 && |dP| >= 5";
+        let e = parse_prop(src).unwrap_err();
+        assert_eq!(&src[e.span.clone()], ">=");
+        let e = parse_prop("always[6](P <= 120").unwrap_err();
+        assert_eq!(e.span, 18..18);
+        assert!(e.message.contains("`)`"));
+        let e = parse_prop("P <= 120 $").unwrap_err();
+        assert_eq!(e.span, 9..10);
+        let e = parse_prop("always[2.5](P <= 1)").unwrap_err();
+        assert_eq!(e.span, 7..10);
+        let rendered = parse_prop("P <=").unwrap_err().render("P <=");
+        assert!(rendered.contains("expected a number"), "{rendered}");
+    }
+
+    #[test]
+    fn unknown_tag_in_closed_registry() {
+        let mut reg = tags::TagRegistry::default();
+        reg.load_str("Seg9.P_in\n").unwrap();
+        let src = "Seg9.P_in <= 3 && |dSeg9.T_out| <= 1";
+        let e = parse_prop_with(src, &mut |n| reg.lookup_or_intern(n)).unwrap_err();
+        assert_eq!(&src[e.span.clone()], "Seg9.T_out");
+        assert!(e.message.contains("unknown tag"));
+        // The process-wide registry is open in tests: new tags are interned.
+        let p = parse_prop("Seg9.P_in <= 3").unwrap();
+        assert_eq!(p.to_string(), "Seg9.P_in <= 3");
+    }
+}
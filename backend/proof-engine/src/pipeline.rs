@@ -0,0 +1,641 @@
+// proof-engine/src/pipeline.rs
+// =============================================================
+// The monitoring loop, independent of where traces come from and where
+// proofs go (`transport`).
+// -------------------------------------------------------------
+// * Each message is parsed, routed to its asset's station, reordered and
+//   judged; verdict changes become proof packets, as does a PASS whose
+//   robustness crosses the property's `alarm_margin`.
+// * The packets and asset checkpoints of consecutive messages go to the
+//   sink as one `Batch` (up to `Settings::txn_messages` messages or
+//   `txn_linger` after the first), with the consumption to `Commit`.
+// * Without checkpoints, a sample held for reordering lives only in
+//   memory, so consumption is committed no further than the oldest
+//   message whose sample is still held (`Held`): a restart reads it again.
+// * A new pack on the watch channel is swapped in between messages and
+//   announced with a `PACK_VERSION_CHANGED` event.
+// * When the source runs dry the samples held for reordering are
+//   released, so a finite trace is judged to its last sample.
+// =============================================================
+
+use crate::cert;
+use crate::dsl::{self, Sample, Truth, Var};
+use crate::engine::{EngineMode, Verdict};
+use crate::monitor::solver_context;
+use crate::pack::{PackDiff, Property, PropertyPack};
+use crate::reorder::LatePolicy;
+use crate::station::{Station, Stations};
+use crate::tags;
+use crate::transport::{Batch, Commit, Position, ProofSink, TraceMsg, TraceSource};
+use crate::witness::{self, Witness};
+use chrono::Utc;
+use serde::Serialize;
+use simd_json::prelude::*;
+use simd_json::BorrowedValue;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::watch;
+use tokio::time::Instant;
+
+/// How stations judge, as configured by the binary.
+#[derive(Clone, Copy, Debug)]
+pub struct Settings {
+    pub mode: EngineMode,
+    pub allowed_lateness_ns: i64,
+    pub late_policy: LatePolicy,
+    /// Most assets judged at once; the least recently seen one is retired
+    /// (its held samples judged, see `Stations::make_room`) to admit another.
+    pub max_assets: usize,
+    /// Most messages per sink transaction.
+    pub txn_messages: usize,
+    /// Longest a message waits in an open transaction.
+    pub txn_linger: Duration,
+}
+
+/// Output of consecutive messages, sent as one transaction.
+#[derive(Default)]
+struct Txn {
+    batch: Batch,
+    /// Per (topic, partition), the offset after the last message taken.
+    consumed: BTreeMap<(String, i32), i64>,
+    messages: usize,
+    /// When the open transaction is sent at the latest.
+    deadline: Option<Instant>,
+}
+
+/// Positions of the messages whose samples reorder buffers still hold,
+/// per asset and sample timestamp.
+#[derive(Default)]
+struct Held(HashMap<String, BTreeMap<i64, Vec<Position>>>);
+
+impl Held {
+    /// Record a sample of `asset` at `ts_ns` read from `position`, once the
+    /// asset's buffer took it: everything at or before the buffer's
+    /// `watermark` has been released (late samples included).
+    fn update(&mut self, asset: &str, ts_ns: i64, position: Option<Position>, watermark: Option<i64>) {
+        let held = self.0.entry(asset.to_string()).or_default();
+        if let Some(watermark) = watermark {
+            *held = held.split_off(&watermark.saturating_add(1));
+        }
+        if let Some(position) = position.filter(|_| watermark.is_none_or(|w| ts_ns > w)) {
+            held.entry(ts_ns).or_default().push(position);
+        }
+        if held.is_empty() {
+            self.0.remove(asset);
+        }
+    }
+
+    /// `asset`'s buffer was flushed.
+    fn release(&mut self, asset: &str) {
+        self.0.remove(asset);
+    }
+
+    /// Oldest held offset per (topic, partition).
+    fn oldest(&self) -> BTreeMap<(String, i32), i64> {
+        let mut oldest = BTreeMap::new();
+        for p in self.0.values().flat_map(BTreeMap::values).flatten() {
+            let offset = oldest.entry((p.topic.clone(), p.partition)).or_insert(p.offset);
+            *offset = (*offset).min(p.offset);
+        }
+        oldest
+    }
+}
+
+/// Send the open transaction, committing what it consumed.  Without
+/// checkpoints a partition resumes no later than its oldest held sample.
+async fn commit(
+    sink: &mut impl ProofSink,
+    source: &impl TraceSource,
+    txn: &mut Txn,
+    held: &Held,
+) -> anyhow::Result<()> {
+    let Txn { batch, mut consumed, .. } = std::mem::take(txn);
+    if !sink.keeps_checkpoints() {
+        for (partition, oldest) in held.oldest() {
+            if let Some(next) = consumed.get_mut(&partition) {
+                *next = (*next).min(oldest);
+            }
+        }
+    }
+    sink.send(batch, Commit { resume: consumed, group: source.group() }).await
+}
+
+#[derive(Serialize)]
+struct ProofPacket {
+    property_id: String,
+    /// Asset whose trace was judged.
+    asset: String,
+    pack_version: String,
+    /// Unix seconds of the oldest / newest sample in the judged window.
+    start_ts: f64,
+    end_ts: f64,
+    trace_hash: String,
+    cert_hash: String,
+    /// PASS, FAIL or INCONCLUSIVE (missing readings, see `dsl::Missing`).
+    verdict: &'static str,
+    verdict_source: EngineMode,
+    /// UNSAT core clause indices (empty unless `verdict_source` is `sat`).
+    core: Vec<usize>,
+    solver_us: Option<u64>,
+    /// Signed distance from flipping the verdict; `None` when vacuous or
+    /// unknown.
+    robustness: Option<f64>,
+    /// Counterexample, on FAIL only.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    witness: Option<Witness>,
+    /// Whether `robustness` is under the property's `alarm_margin` while it
+    /// passes; absent without a margin.  A PASS after a PASS was published
+    /// because this flipped.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    margin_alarm: Option<bool>,
+    /// Revises an earlier verdict in light of a late sample (`LATE_POLICY=correct`).
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    correction: bool,
+}
+
+/// Packet for `property`'s verdict on its horizon of `window` (newest-first).
+fn proof_packet(
+    property: &Property,
+    asset: &str,
+    pack_version: &str,
+    window: &[Sample],
+    stamps: &[i64],
+    verdict: Verdict,
+    correction: bool,
+) -> anyhow::Result<ProofPacket> {
+    // Certificate over the window this property was judged on
+    let n = property.horizon.window_len(stamps);
+    let (judged, stamps) = (&window[..n], &stamps[..n]);
+    let robustness = Some(dsl::robustness_timed(&property.prop, judged, stamps, property.missing)).filter(|r| r.is_finite());
+    Ok(ProofPacket {
+        property_id: property.id.clone(),
+        asset: asset.to_string(),
+        pack_version: pack_version.to_string(),
+        start_ts: unix_secs(stamps[n - 1]),
+        end_ts: unix_secs(stamps[0]),
+        trace_hash: cert::trace_hash(judged, stamps),
+        cert_hash: cert::cert_hash(&property.prop, judged, stamps)?,
+        verdict: verdict.truth.verdict(),
+        verdict_source: verdict.source,
+        core: verdict.core,
+        solver_us: verdict.solver_us,
+        robustness,
+        margin_alarm: property.alarm_margin.map(|m| verdict.truth == Truth::True && robustness.is_some_and(|r| r < m)),
+        witness: match verdict.truth {
+            Truth::False => witness::explain(&property.prop, judged, stamps, property.missing),
+            _ => None,
+        },
+        correction,
+    })
+}
+
+// ------------------------------------------------------------------
+// Fast zero‑copy parse using `simd-json` BorrowedValue.
+// Expected payload: {"ts":1688145051.25,"tags":{"P":75.2,"T":24.1}}
+// with `ts` in Unix seconds, integral or fractional, and optionally
+// "asset":"seg-7"; without it the transport's key names the asset.
+// Returns (asset, timestamp in ns, Sample).  Tags missing from a closed registry are
+// reported by `tags::resolve` and left out of the sample.
+// ------------------------------------------------------------------
+
+#[inline]
+fn parse_trace(msg: &TraceMsg) -> Option<(String, i64, HashMap<Var, f64>)> {
+    // Safety: simd-json expects &mut [u8]
+    let mut buf = msg.payload.clone();
+    let v: BorrowedValue<'_> = simd_json::to_borrowed_value(&mut buf).ok()?;
+    let obj = v.as_object()?;
+    let ts = obj.get("ts")?;
+    let ts_ns = match ts.as_i64() {
+        Some(secs) => secs.checked_mul(1_000_000_000)?,
+        None => {
+            let ns = (ts.as_f64()? * 1e9).round();
+            // `as` saturates; keep only values that fit
+            (ns.is_finite() && ns.abs() < i64::MAX as f64).then_some(ns as i64)?
+        }
+    };
+    let asset = match obj.get("asset") {
+        Some(a) => a.as_str()?.to_string(),
+        None => msg.key.as_deref().unwrap_or(DEFAULT_ASSET).to_string(),
+    };
+    let tags = obj.get("tags")?.as_object()?;
+
+    let mut sample = HashMap::with_capacity(tags.len());
+    for (k, v) in tags.iter() {
+        if let Some(var) = tags::resolve(k.as_ref()) {
+            if let Some(f) = v.cast_f64() {
+                sample.insert(var, f);
+            }
+        }
+    }
+    Some((asset, ts_ns, sample))
+}
+
+/// Asset of messages that name none.
+const DEFAULT_ASSET: &str = "default";
+
+fn unix_secs(ts_ns: i64) -> f64 {
+    ts_ns.div_euclid(1_000_000_000) as f64 + ts_ns.rem_euclid(1_000_000_000) as f64 / 1e9
+}
+
+/// Announces on the proof topic which rule set produces the packets that
+/// follow it.
+#[derive(Serialize)]
+struct PackVersionChanged<'a> {
+    event: &'static str,
+    ts: i64,
+    old_version: &'a str,
+    new_version: &'a str,
+    #[serde(flatten)]
+    diff: &'a PackDiff,
+}
+
+/// Judge every message of `source` against the pack on `packs` and
+/// publish to `sink`.  Checkpoints the sink keeps are restored first.
+/// Returns once the source is exhausted.
+pub async fn run(
+    source: &mut impl TraceSource,
+    sink: &mut impl ProofSink,
+    settings: Settings,
+    mut packs: watch::Receiver<Arc<PropertyPack>>,
+) -> anyhow::Result<()> {
+    let mut pack = packs.borrow_and_update().clone();
+
+    // One window per asset (longest bound horizon), one solver context
+    let ctx = solver_context();
+    let mut stations =
+        Stations::new(&ctx, settings.mode, settings.allowed_lateness_ns).max_assets(settings.max_assets);
+    let mut late_samples: u64 = 0;
+    let mut txn = Txn::default();
+    let mut held = Held::default();
+    let mut snapshots = sink.restore().await?;
+    if !snapshots.is_empty() {
+        log::info!("restoring {} assets from checkpoints", snapshots.len());
+    }
+    // Within `max_assets`, the most recently sampled assets come back; the
+    // rest are retired with their checkpoints
+    snapshots.sort_by_key(|s| std::cmp::Reverse(s.newest));
+    for snapshot in snapshots.split_off(settings.max_assets.min(snapshots.len())) {
+        log::warn!("{} assets in flight: retiring {:?} from checkpoints", settings.max_assets, snapshot.asset);
+        txn.batch.retire(&snapshot.asset);
+    }
+    for snapshot in snapshots {
+        stations.restore(snapshot, &pack);
+    }
+
+    loop {
+        let deadline = txn.deadline;
+        let msg = tokio::select! {
+            Ok(()) = packs.changed() => {
+                // Swap between messages: windows survive, and monitor and
+                // verdict state carry over for unchanged rules.
+                let next = packs.borrow_and_update().clone();
+                let diff = pack.diff(&next);
+                stations.reload(&pack, &next);
+                log::info!(
+                    "property pack {} -> {}: {} added, {} removed, {} changed",
+                    pack.version, next.version, diff.added.len(), diff.removed.len(), diff.changed.len()
+                );
+                let event = PackVersionChanged {
+                    event: "PACK_VERSION_CHANGED",
+                    ts: Utc::now().timestamp(),
+                    old_version: &pack.version,
+                    new_version: &next.version,
+                    diff: &diff,
+                };
+                txn.batch.packets.push(serde_json::to_vec(&event)?);
+                if sink.keeps_checkpoints() {
+                    for snapshot in stations.assets().filter_map(|a| stations.snapshot(a, &next)) {
+                        txn.batch.checkpoint(snapshot);
+                    }
+                }
+                commit(sink, source, &mut txn, &held).await?;
+                pack = next;
+                continue;
+            }
+            () = tokio::time::sleep_until(deadline.unwrap_or_else(Instant::now)), if deadline.is_some() => {
+                commit(sink, source, &mut txn, &held).await?;
+                continue;
+            }
+            msg = source.next() => msg?,
+        };
+        let Some(msg) = msg else { break };
+
+        // The packets a message produces, the asset's checkpoint and the
+        // message's consumption commit in the same transaction;
+        // unparseable messages only add their consumption
+        if let Some(trace) = parse_trace(&msg) {
+            let (asset, ts_ns) = (trace.0.clone(), trace.1);
+            if let Some((old, mut station)) = stations.make_room(&asset) {
+                log::warn!("{} assets in flight: retiring {old:?} to admit {asset:?}", settings.max_assets);
+                let released = station.reorder.flush();
+                held.release(&old);
+                tick(&mut station, &pack, &old, released, &mut txn.batch.packets)?;
+                if sink.keeps_checkpoints() {
+                    txn.batch.retire(&old);
+                }
+            }
+            txn.batch.packets.extend(process(&mut stations, &pack, settings.late_policy, &mut late_samples, trace)?);
+            let watermark = stations.get(&asset).and_then(|s| s.reorder.watermark());
+            held.update(&asset, ts_ns, msg.position.clone(), watermark);
+            if let Some(snapshot) = stations.snapshot(&asset, &pack).filter(|_| sink.keeps_checkpoints()) {
+                txn.batch.checkpoint(snapshot);
+            }
+        }
+        if let Some(p) = msg.position {
+            txn.consumed.insert((p.topic, p.partition), p.offset + 1);
+        }
+        txn.messages += 1;
+        txn.deadline.get_or_insert_with(|| Instant::now() + settings.txn_linger);
+        if txn.messages >= settings.txn_messages {
+            commit(sink, source, &mut txn, &held).await?;
+        }
+    }
+
+    // Nothing more will advance the watermarks: judge what they hold
+    let mut assets: Vec<String> = stations.assets().cloned().collect();
+    assets.sort();
+    for asset in assets {
+        let station = stations.entry(&asset, &pack);
+        let released = station.reorder.flush();
+        held.release(&asset);
+        tick(station, &pack, &asset, released, &mut txn.batch.packets)?;
+        if let Some(snapshot) = stations.snapshot(&asset, &pack).filter(|_| sink.keeps_checkpoints()) {
+            txn.batch.checkpoint(snapshot);
+        }
+    }
+    commit(sink, source, &mut txn, &held).await
+}
+
+/// Run one trace sample through its asset's station and return the proof
+/// packets it produces, serialized.
+fn process(
+    stations: &mut Stations<'_>,
+    pack: &PropertyPack,
+    late_policy: LatePolicy,
+    late_samples: &mut u64,
+    (asset, ts_ns, sample): (String, i64, Sample),
+) -> anyhow::Result<Vec<Vec<u8>>> {
+    let station = stations.entry(&asset, pack);
+    let mut out = Vec::new();
+
+    // Hold samples until the asset's watermark passes them, so its
+    // window sees them in timestamp order
+    let released = match station.reorder.push(ts_ns, sample) {
+        Ok(released) => released,
+        Err(late) => {
+            *late_samples += 1;
+            log::warn!(
+                "late sample of {asset:?} at {} behind {} ({late_samples} so far): {late_policy}",
+                unix_secs(late.ts_ns),
+                unix_secs(late.released)
+            );
+            match late_policy {
+                LatePolicy::Drop => {}
+                LatePolicy::Reevaluate => {
+                    let verdicts = station.engine.insert_late(late.ts_ns, late.item);
+                    publish(station, pack, &asset, verdicts, &mut out)?;
+                }
+                LatePolicy::Correct => {
+                    // Live state stays as it is; only the report is revised
+                    let (window, stamps) = station.engine.monitors().with_sample(late.ts_ns, late.item);
+                    for (k, verdict) in station.engine.judge(&window, &stamps).into_iter().enumerate() {
+                        if verdict.truth != station.prev[k] {
+                            let property = &pack.properties[station.bound[k]];
+                            let packet =
+                                proof_packet(property, &asset, &pack.version, &window, &stamps, verdict, true)?;
+                            out.push(serde_json::to_vec(&packet)?);
+                        }
+                    }
+                }
+            }
+            return Ok(out);
+        }
+    };
+
+    tick(station, pack, &asset, released, &mut out)?;
+    Ok(out)
+}
+
+/// Feed released samples to the station, appending a packet for every
+/// verdict change or margin alarm.
+fn tick(
+    station: &mut Station<'_>,
+    pack: &PropertyPack,
+    asset: &str,
+    released: Vec<(i64, Sample)>,
+    out: &mut Vec<Vec<u8>>,
+) -> anyhow::Result<()> {
+    for (ts_ns, sample) in released {
+        // Each property sees its own horizon of the asset's window
+        let verdicts = station.engine.tick(ts_ns, sample);
+        publish(station, pack, asset, verdicts, out)?;
+    }
+    Ok(())
+}
+
+/// Append a packet for every property whose verdict changed, or whose
+/// margin alarm was raised or cleared under a PASS, and record what was
+/// published.
+fn publish(
+    station: &mut Station<'_>,
+    pack: &PropertyPack,
+    asset: &str,
+    verdicts: Vec<Verdict>,
+    out: &mut Vec<Vec<u8>>,
+) -> anyhow::Result<()> {
+    let (window, stamps) = (station.engine.window(), station.engine.timestamps());
+    for (k, verdict) in verdicts.into_iter().enumerate() {
+        let property = &pack.properties[station.bound[k]];
+        let alarm = match property.alarm_margin {
+            Some(margin) if verdict.truth == Truth::True => {
+                let n = property.horizon.window_len(stamps);
+                dsl::robustness_timed(&property.prop, &window[..n], &stamps[..n], property.missing) < margin
+            }
+            _ => false,
+        };
+        if verdict.truth != station.prev[k] || alarm != station.alarms[k] {
+            station.prev[k] = verdict.truth;
+            station.alarms[k] = alarm;
+            let packet = proof_packet(property, asset, &pack.version, window, stamps, verdict, false)?;
+            out.push(serde_json::to_vec(&packet)?);
+        }
+    }
+    Ok(())
+}
+
+
+// ---------------------------
+// Unit tests
+// ---------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::{JsonlSource, MemorySink, MemorySource};
+
+    const PACK: &str = r#"
+        version = "t-1"
+
+        [assets]
+        seg7 = "segment"
+
+        [[property]]
+        id = "ramp"
+        severity = "warning"
+        asset_class = "segment"
+        expr = "|dP| <= 1"
+
+        [[property]]
+        id = "max_pressure"
+        severity = "critical"
+        expr = "P <= 120"
+    "#;
+
+    /// Two assets interleaved on one stream, seg7's 3 s sample arriving late.
+    const TRACE: &[u8] = br#"
+{"asset":"seg7","ts":0,"tags":{"P":50}}
+{"asset":"cs2","ts":0,"tags":{"P":100}}
+{"asset":"seg7","ts":1,"tags":{"P":51}}
+{"asset":"cs2","ts":1,"tags":{"P":125}}
+not a trace
+{"asset":"seg7","ts":2,"tags":{"P":130}}
+{"asset":"seg7","ts":4,"tags":{"P":100}}
+{"asset":"seg7","ts":3,"tags":{"P":101}}
+{"asset":"cs2","ts":2,"tags":{"P":110}}
+"#;
+
+    fn settings(lateness_s: i64) -> Settings {
+        let allowed_lateness_ns = lateness_s * 1_000_000_000;
+        Settings {
+            mode: EngineMode::Rust,
+            allowed_lateness_ns,
+            late_policy: LatePolicy::Drop,
+            max_assets: usize::MAX,
+            txn_messages: 1,
+            txn_linger: Duration::from_secs(1),
+        }
+    }
+
+    fn packs() -> watch::Receiver<Arc<PropertyPack>> {
+        watch::channel(Arc::new(PropertyPack::from_str(PACK, 2).unwrap())).1
+    }
+
+    /// `(asset, property, verdict, end_ts)` of each packet.
+    fn summary(sink: &MemorySink) -> Vec<(String, String, String, f64)> {
+        sink.packets
+            .iter()
+            .map(|p| serde_json::from_slice::<serde_json::Value>(p).unwrap())
+            .map(|p| {
+                let s = |k: &str| p[k].as_str().unwrap().to_string();
+                (s("asset"), s("property_id"), s("verdict"), p["end_ts"].as_f64().unwrap())
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn judges_a_jsonl_trace_end_to_end() {
+        let mut sink = MemorySink::default();
+        run(&mut JsonlSource::new(TRACE), &mut sink, settings(1), packs()).await.unwrap();
+        let expected = [
+            ("seg7", "ramp", "FAIL", 2.0),
+            ("seg7", "max_pressure", "FAIL", 2.0),
+            // A FAIL holds while its tick is inside the 2-sample horizon
+            ("cs2", "max_pressure", "FAIL", 1.0),
+            // End of input releases what the watermarks still held
+            ("seg7", "max_pressure", "PASS", 4.0),
+        ];
+        let expected: Vec<_> =
+            expected.iter().map(|&(a, p, v, t)| (a.to_string(), p.to_string(), v.to_string(), t)).collect();
+        assert_eq!(summary(&sink), expected);
+        assert!(sink.checkpoints.contains_key("cs2"));
+    }
+
+    #[tokio::test]
+    async fn publishes_margin_alarms_under_pass() {
+        let pack = PACK.replace("expr = \"P <= 120\"", "alarm_margin = 5.0\n        expr = \"P <= 120\"");
+        let packs = watch::channel(Arc::new(PropertyPack::from_str(&pack, 2).unwrap())).1;
+        let trace: String = [100, 110, 116, 118, 110]
+            .iter()
+            .enumerate()
+            .map(|(ts, p)| format!("{{\"asset\":\"cs2\",\"ts\":{ts},\"tags\":{{\"P\":{p}}}}}\n"))
+            .collect();
+        let mut sink = MemorySink::default();
+        run(&mut JsonlSource::new(std::io::Cursor::new(trace)), &mut sink, settings(0), packs).await.unwrap();
+        // Still PASS throughout: the margin going under 5 bar and back is published
+        let packets: Vec<serde_json::Value> = sink.packets.iter().map(|p| serde_json::from_slice(p).unwrap()).collect();
+        let alarms: Vec<_> = packets
+            .iter()
+            .map(|p| (p["verdict"].as_str().unwrap(), p["end_ts"].as_f64().unwrap(), p["margin_alarm"].as_bool()))
+            .collect();
+        assert_eq!(alarms, [("PASS", 2.0, Some(true)), ("PASS", 4.0, Some(false))]);
+        assert_eq!(packets[0]["robustness"], 4.0);
+    }
+
+    #[tokio::test]
+    async fn commits_no_further_than_held_samples() {
+        let feed = || {
+            let (tx, source) = MemorySource::channel();
+            for ts in 0..3 {
+                let mut msg = TraceMsg::new(format!("{{\"asset\":\"seg7\",\"ts\":{ts},\"tags\":{{\"P\":100}}}}"));
+                msg.position = Some(Position { topic: "plc.trace".into(), partition: 0, offset: ts });
+                tx.send(msg).unwrap();
+            }
+            source
+        };
+        let resumes = |sink: &MemorySink| -> Vec<Option<i64>> {
+            sink.commits.iter().map(|c| c.get(&("plc.trace".to_string(), 0)).copied()).collect()
+        };
+
+        // Each sample waits 1 s for the watermark: only checkpoints keep it
+        let mut sink = MemorySink { no_checkpoints: true, ..Default::default() };
+        run(&mut feed(), &mut sink, settings(1), packs()).await.unwrap();
+        assert_eq!(resumes(&sink), [Some(0), Some(1), Some(2), None]);
+        let mut sink = MemorySink::default();
+        run(&mut feed(), &mut sink, settings(1), packs()).await.unwrap();
+        assert_eq!(resumes(&sink), [Some(1), Some(2), Some(3), None]);
+
+        // Two messages per transaction
+        let mut sink = MemorySink::default();
+        run(&mut feed(), &mut sink, Settings { txn_messages: 2, ..settings(1) }, packs()).await.unwrap();
+        assert_eq!(resumes(&sink), [Some(2), Some(3)]);
+    }
+
+    #[tokio::test]
+    async fn resumes_from_the_sinks_checkpoints() {
+        let lines: Vec<&'static [u8]> = TRACE.split(|&b| b == b'\n').collect();
+        let feed = |lines: &[&'static [u8]]| {
+            let (tx, source) = MemorySource::channel();
+            for &line in lines {
+                tx.send(TraceMsg::new(line)).unwrap();
+            }
+            source
+        };
+        let mut whole = MemorySink::default();
+        run(&mut feed(&lines), &mut whole, settings(0), packs()).await.unwrap();
+
+        // Stop after seg7 went FAIL; the second run must not report it again
+        let mut sink = MemorySink::default();
+        run(&mut feed(&lines[..7]), &mut sink, settings(0), packs()).await.unwrap();
+        assert_eq!(sink.checkpoints.len(), 2);
+        run(&mut feed(&lines[7..]), &mut sink, settings(0), packs()).await.unwrap();
+        assert_eq!(summary(&sink), summary(&whole));
+    }
+
+    #[tokio::test]
+    async fn restores_no_more_than_max_assets() {
+        let mut sink = MemorySink::default();
+        run(&mut JsonlSource::new(TRACE), &mut sink, settings(0), packs()).await.unwrap();
+        assert_eq!(sink.checkpoints.keys().collect::<Vec<_>>(), ["cs2", "seg7"]);
+
+        // seg7 was sampled last: it comes back, cs2's checkpoint is deleted
+        let one = Settings { max_assets: 1, ..settings(0) };
+        let (_, mut empty) = MemorySource::channel();
+        run(&mut empty, &mut sink, one, packs()).await.unwrap();
+        assert_eq!(sink.checkpoints.keys().collect::<Vec<_>>(), ["seg7"]);
+
+        // Retiring seg7 for a new asset deletes its checkpoint too
+        let trace = &b"{\"asset\":\"cs2\",\"ts\":5,\"tags\":{\"P\":100}}\n"[..];
+        run(&mut JsonlSource::new(trace), &mut sink, one, packs()).await.unwrap();
+        assert_eq!(sink.checkpoints.keys().collect::<Vec<_>>(), ["cs2"]);
+    }
+}
@@ -0,0 +1,189 @@
+// proof-engine/src/reorder.rs
+// =============================================================
+// Event-time reordering in front of the trace window.
+// -------------------------------------------------------------
+// * Samples are held until the **watermark** – the newest timestamp seen
+//   minus the allowed lateness – passes them, then released oldest first.
+//   Anything that arrives within the lateness is slotted into place.
+// * A sample older than one already released is **late**: the window has
+//   moved past it.  It is handed back with a `LatePolicy` deciding its fate.
+// * Allowed lateness 0 releases every sample on arrival; only stragglers
+//   behind the newest sample are late.
+// =============================================================
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
+/// What to do with a sample that arrives behind the released stream.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LatePolicy {
+    /// Discard it (logged and counted).
+    #[default]
+    Drop,
+    /// Slot it into the window at its time position and re-judge the
+    /// newest tick on the corrected window, in the engine's mode.
+    Reevaluate,
+    /// Leave live state alone; publish correction packets for properties
+    /// whose verdict on the corrected window differs from the reported one.
+    Correct,
+}
+
+impl FromStr for LatePolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "drop" => Ok(LatePolicy::Drop),
+            "reevaluate" => Ok(LatePolicy::Reevaluate),
+            "correct" => Ok(LatePolicy::Correct),
+            other => Err(format!("unknown late policy {other:?} (expected `drop`, `reevaluate` or `correct`)")),
+        }
+    }
+}
+
+impl fmt::Display for LatePolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            LatePolicy::Drop => "drop",
+            LatePolicy::Reevaluate => "reevaluate",
+            LatePolicy::Correct => "correct",
+        })
+    }
+}
+
+/// A sample that arrived after the stream moved past it.
+#[derive(Debug, PartialEq)]
+pub struct Late<T> {
+    pub ts_ns: i64,
+    pub item: T,
+    /// Timestamp of the newest sample already released.
+    pub released: i64,
+}
+
+pub struct ReorderBuffer<T> {
+    lateness_ns: i64,
+    /// Keyed by timestamp, then arrival order for equal timestamps.
+    pending: BTreeMap<(i64, u64), T>,
+    arrivals: u64,
+    newest: Option<i64>,
+    released: Option<i64>,
+}
+
+impl<T> ReorderBuffer<T> {
+    pub fn new(allowed_lateness_ns: i64) -> Self {
+        ReorderBuffer {
+            lateness_ns: allowed_lateness_ns.max(0),
+            pending: BTreeMap::new(),
+            arrivals: 0,
+            newest: None,
+            released: None,
+        }
+    }
+
+    /// Newest timestamp seen minus the allowed lateness.
+    pub fn watermark(&self) -> Option<i64> {
+        self.newest.map(|t| t.saturating_sub(self.lateness_ns))
+    }
+
+    /// Samples waiting for the watermark.
+    pub fn pending(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Waiting samples, oldest first.
+    pub fn pending_items(&self) -> impl Iterator<Item = (i64, &T)> {
+        self.pending.iter().map(|(&(ts, _), item)| (ts, item))
+    }
+
+    /// Newest timestamp seen.
+    pub fn newest(&self) -> Option<i64> {
+        self.newest
+    }
+
+    /// Timestamp of the newest sample released.
+    pub fn released(&self) -> Option<i64> {
+        self.released
+    }
+
+    /// A buffer in the state `newest`, `released` and `pending_items`
+    /// describe, e.g. from a checkpoint.
+    pub fn resume(
+        allowed_lateness_ns: i64,
+        newest: Option<i64>,
+        released: Option<i64>,
+        pending: impl IntoIterator<Item = (i64, T)>,
+    ) -> Self {
+        let mut buf = ReorderBuffer::new(allowed_lateness_ns);
+        for (ts_ns, item) in pending {
+            buf.pending.insert((ts_ns, buf.arrivals), item);
+            buf.arrivals += 1;
+        }
+        buf.newest = newest;
+        buf.released = released;
+        buf
+    }
+
+    /// Accept a sample; returns the samples the advanced watermark
+    /// releases, oldest first, or the sample itself if it is late.
+    pub fn push(&mut self, ts_ns: i64, item: T) -> Result<Vec<(i64, T)>, Late<T>> {
+        if let Some(released) = self.released.filter(|&r| ts_ns < r) {
+            return Err(Late { ts_ns, item, released });
+        }
+        self.pending.insert((ts_ns, self.arrivals), item);
+        self.arrivals += 1;
+        self.newest = Some(self.newest.map_or(ts_ns, |t| t.max(ts_ns)));
+        let watermark = self.watermark().expect("set above");
+        let keep = self.pending.split_off(&(watermark.saturating_add(1), 0));
+        let ready = std::mem::replace(&mut self.pending, keep);
+        if let Some((&(ts, _), _)) = ready.last_key_value() {
+            self.released = Some(ts);
+        }
+        Ok(ready.into_iter().map(|((ts, _), item)| (ts, item)).collect())
+    }
+
+    /// Release everything still pending, oldest first.
+    pub fn flush(&mut self) -> Vec<(i64, T)> {
+        let ready = std::mem::take(&mut self.pending);
+        if let Some((&(ts, _), _)) = ready.last_key_value() {
+            self.released = Some(ts);
+        }
+        ready.into_iter().map(|((ts, _), item)| (ts, item)).collect()
+    }
+}
+
+// ---------------------------
+// Unit tests
+// ---------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ts(released: Result<Vec<(i64, char)>, Late<char>>) -> Vec<i64> {
+        released.unwrap().into_iter().map(|(t, _)| t).collect()
+    }
+
+    #[test]
+    fn reorders_within_lateness_and_rejects_late_samples() {
+        let mut buf = ReorderBuffer::new(10);
+        assert_eq!(ts(buf.push(100, 'a')), [] as [i64; 0]);
+        assert_eq!(ts(buf.push(120, 'c')), [100]);
+        // 105 is behind 120 but ahead of the watermark (110): slotted in
+        assert_eq!(ts(buf.push(105, 'b')), [105]);
+        assert_eq!(buf.watermark(), Some(110));
+        assert_eq!(ts(buf.push(131, 'd')), [120]);
+        assert_eq!(buf.push(119, 'x'), Err(Late { ts_ns: 119, item: 'x', released: 120 }));
+        assert_eq!(buf.pending(), 1);
+        assert_eq!(buf.flush(), [(131, 'd')]);
+    }
+
+    #[test]
+    fn zero_lateness_passes_samples_straight_through() {
+        let mut buf = ReorderBuffer::new(0);
+        assert_eq!(ts(buf.push(5, 'a')), [5]);
+        assert_eq!(ts(buf.push(5, 'b')), [5]);
+        assert!(buf.push(4, 'c').is_err());
+        assert_eq!("Correct".parse(), Ok(LatePolicy::Correct));
+        assert!("ignore".parse::<LatePolicy>().is_err());
+    }
+}
@@ -16,15 +16,18 @@
 // -------------
 // * Default build gives a *naïve* implementation: on every call we
 //   `reset()` the solver then re‑assert all live clauses.
-// * Build with `--features model-reuse` to enable **incremental model
-//   reuse** via `solver.push()/pop()` and assumption literals.  This
-//   cuts observed latency ~3× on synthetic workloads (see benchmarks).
+// * Build with `--features model-reuse` to default to **incremental model
+//   reuse**: one guard literal per clause, asserted once, with evicted
+//   clauses retired by dropping their assumption.  The solver (and its
+//   learned clauses) lives across ticks.  `sat_window` in
+//   benches/engine_bench.rs compares the two modes.
+// * Either mode can be picked at runtime with `SatCore::with_mode`.
 // =============================================================
 
 #![allow(clippy::needless_return)]
 
 use z3::{ast::Bool, Context, Solver};
-use std::collections::VecDeque;
+use std::collections::{HashMap, HashSet, VecDeque};
 use thiserror::Error;
 
 // ---------------------------
@@ -59,39 +62,111 @@ pub enum SatError {
 // SatCore
 // ---------------------------
 
+/// How `unsat_recycle` talks to Z3.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SolveMode {
+    /// `reset()` and re‑assert the whole window on every call.
+    Naive,
+    /// Keep the solver alive; assert `α → c` once per clause and retire
+    /// evicted clauses by dropping (and negating) their `α`.
+    Incremental,
+}
+
+impl Default for SolveMode {
+    fn default() -> Self {
+        if cfg!(feature = "model-reuse") {
+            SolveMode::Incremental
+        } else {
+            SolveMode::Naive
+        }
+    }
+}
+
+/// Retired clauses tolerated (as a multiple of the live window) before the
+/// incremental solver is rebuilt to shed dead clauses and vars.
+const COMPACT_FACTOR: usize = 8;
+
 /// Incremental SAT wrapper with clause recycling.
 pub struct SatCore<'ctx> {
     ctx: &'ctx Context,
     solver: Solver<'ctx>,
-    /// Sliding window of active clauses (size ≤ H ⋅ |Δ|).
-    clauses: VecDeque<Clause>,
+    mode: SolveMode,
+    /// Sliding window of active clauses (size ≤ H ⋅ |Δ|), each paired with
+    /// its indicator literal `α` (the solver sees `α → clause`).
+    clauses: VecDeque<(Clause, Bool<'ctx>)>,
     /// Maximum number of clauses to keep (capacity).
     cap: usize,
-    /// Scratch bool variables – indexed by `var` id.
-    vars: Vec<Bool<'ctx>>,
+    /// Clause count of each `unsat_recycle` batch in the window, oldest first.
+    batches: VecDeque<usize>,
+    /// Maximum number of batches (ticks) to keep; see `batch_window`.
+    max_batches: usize,
+    /// Scratch bool variables – keyed by `var` id.
+    vars: HashMap<i32, Bool<'ctx>>,
+    /// Id of the next indicator literal.
+    next_guard: u64,
+    /// Clauses retired since the solver was last rebuilt (incremental mode).
+    retired: usize,
+    /// Minimised UNSAT core of the last call, as window indices.
+    last_core: Option<Vec<usize>>,
 }
 
 impl<'ctx> SatCore<'ctx> {
-    /// Create a new SAT core with capacity `cap` clauses.
+    /// Create a new SAT core with capacity `cap` clauses, using the mode
+    /// selected by the `model-reuse` feature.
     pub fn new(ctx: &'ctx Context, cap: usize) -> Self {
+        Self::with_mode(ctx, cap, SolveMode::default())
+    }
+
+    /// Create a new SAT core with an explicit solve mode.
+    pub fn with_mode(ctx: &'ctx Context, cap: usize, mode: SolveMode) -> Self {
         Self {
             ctx,
             solver: Solver::new(ctx),
-            clauses: VecDeque::with_capacity(cap + 8),
+            mode,
+            clauses: VecDeque::with_capacity(cap.min(1024) + 8),
             cap,
-            vars: Vec::new(),
+            batches: VecDeque::new(),
+            max_batches: usize::MAX,
+            vars: HashMap::new(),
+            next_guard: 0,
+            retired: 0,
+            last_core: None,
         }
     }
 
+    /// Also age clauses out per *batch*: keep at most `ticks` calls of
+    /// `unsat_recycle`, whatever their size.  A violation encoded in one
+    /// batch therefore stays UNSAT for exactly `ticks` calls.
+    pub fn batch_window(mut self, ticks: usize) -> Self {
+        self.max_batches = ticks;
+        self
+    }
+
+    /// Resize the batch window; takes effect at the next `unsat_recycle`.
+    pub fn set_batch_window(&mut self, ticks: usize) {
+        self.max_batches = ticks;
+    }
+
+    pub fn mode(&self) -> SolveMode {
+        self.mode
+    }
+
+    /// Number of clauses currently in the window.
+    pub fn len(&self) -> usize {
+        self.clauses.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.clauses.is_empty()
+    }
+
     /// Get (or create) a Z3 boolean var by index.
     fn get_var(&mut self, idx: i32) -> Bool<'ctx> {
-        let uidx: usize = idx as usize;
-        let len = self.vars.len();
-        if uidx >= self.vars.len() {
-            let diff = uidx + 1 - self.vars.len();
-            self.vars.extend((0..diff).map(|i| Bool::new_const(self.ctx, format!("p{}", len + i))));
-        }
-        self.vars[uidx].clone()
+        let ctx = self.ctx;
+        self.vars
+            .entry(idx)
+            .or_insert_with(|| Bool::new_const(ctx, format!("p{}", idx)))
+            .clone()
     }
 
     /// Translate a `Clause` to a Z3 AST.
@@ -104,86 +179,182 @@ impl<'ctx> SatCore<'ctx> {
         Bool::or(self.ctx, &refs)
     }
 
-    /// Insert new clause, popping the oldest if over capacity.
-    fn push_clause(&mut self, clause: Clause) {
-        self.clauses.push_back(clause);
-        if self.clauses.len() > self.cap {
-            self.clauses.pop_front();
+    /// Drop the oldest clause.  In incremental mode its `α` is asserted false
+    /// so the solver can simplify the dead clause away.
+    fn evict_oldest(&mut self) {
+        if let Some((_, guard)) = self.clauses.pop_front() {
+            if let Some(front) = self.batches.front_mut() {
+                *front -= 1;
+                if *front == 0 {
+                    self.batches.pop_front();
+                }
+            }
+            if self.mode == SolveMode::Incremental {
+                self.solver.assert(&guard.not());
+                self.retired += 1;
+            }
         }
     }
 
-    /// Naïve solve: rebuild entire solver from scratch.
-    fn solve_naive(&mut self) -> SatResult {
-        self.solver.reset();
-        let snapshot: Vec<Clause> = self.clauses.iter().cloned().collect();
-        for cl in snapshot {
-            let ast = self.clause_to_ast(&cl);
-            self.solver.assert(&ast);
+    /// Insert new clause with a fresh indicator literal.  The oldest clause is
+    /// evicted *first*, so the window never exceeds `cap` during a check.
+    fn push_clause(&mut self, clause: Clause) {
+        if self.cap == 0 {
+            return;
         }
-        match self.solver.check() {
-            z3::SatResult::Sat => SatResult::Sat,
-            z3::SatResult::Unsat => SatResult::Unsat,
-            z3::SatResult::Unknown => SatResult::Unknown,
+        while self.clauses.len() >= self.cap {
+            self.evict_oldest();
+        }
+        let guard = Bool::new_const(self.ctx, format!("a{}", self.next_guard));
+        self.next_guard += 1;
+        if self.mode == SolveMode::Incremental {
+            let ast = self.clause_to_ast(&clause);
+            self.solver.assert(&guard.implies(&ast));
+        }
+        self.clauses.push_back((clause, guard));
+        if let Some(back) = self.batches.back_mut() {
+            *back += 1;
         }
     }
 
-    /// Incremental solve with model reuse (feature‑gated).
-    #[cfg(feature = "model-reuse")]
-    fn solve_incremental(&mut self, new_batch: &[Clause]) -> SatResult {
-        // Strategy: keep solver in sync; for each new clause `c`:
-        //  * create fresh assumption literal `α`
-        //  * assert (¬α ∨ c) permanently
-        //  * push α into `assumptions` vector
-        // When a clause is evicted, we also drop its α from assumptions.
-        // Reference: Eén & Sörensson, "Translating Pseudo Boolean Constraints into SAT" (2006)
-        use z3::ast::Dynamic;
-        let mut assumptions: Vec<Bool<'ctx>> = Vec::with_capacity(self.clauses.len());
-        self.solver.reset();
+    /// Start a new batch, evicting whole batches beyond `max_batches`.
+    fn open_batch(&mut self) {
+        if self.max_batches == 0 {
+            return;
+        }
+        while self.batches.len() >= self.max_batches {
+            let n = self.batches[0];
+            for _ in 0..n {
+                self.evict_oldest();
+            }
+            if n == 0 {
+                self.batches.pop_front();
+            }
+        }
+        self.batches.push_back(0);
+    }
 
-        // Re‑assert surviving window.
-        for cl in &self.clauses {
-            let ast = self.clause_to_ast(cl);
-            self.solver.assert(&ast);
+    /// Drop the newest batch whole, e.g. to replace it with a re-judged one.
+    pub fn pop_batch(&mut self) {
+        let Some(n) = self.batches.pop_back() else { return };
+        for _ in 0..n {
+            if let Some((_, guard)) = self.clauses.pop_back() {
+                if self.mode == SolveMode::Incremental {
+                    self.solver.assert(&guard.not());
+                    self.retired += 1;
+                }
+            }
         }
+    }
 
-        // Add new batch after window shift.
-        for cl in new_batch.iter() {
+    /// Assert `α → clause` for every clause in the window.
+    fn assert_window(&mut self) {
+        let snapshot: Vec<(Clause, Bool<'ctx>)> = self.clauses.iter().cloned().collect();
+        for (cl, guard) in &snapshot {
             let ast = self.clause_to_ast(cl);
-            self.solver.assert(&ast);
+            self.solver.assert(&guard.implies(&ast));
         }
+    }
 
-        // Store.
-        for cl in new_batch.to_vec() {
-            self.push_clause(cl);
-        }
+    /// Indicator literals of the live window, oldest first.
+    fn live_guards(&self) -> Vec<Bool<'ctx>> {
+        self.clauses.iter().map(|(_, guard)| guard.clone()).collect()
+    }
+
+    /// Forget Z3 vars that no live clause mentions.
+    fn prune_vars(&mut self) {
+        let live: HashSet<i32> = self.clauses.iter().flat_map(|(c, _)| c.0.iter().map(|l| l.var)).collect();
+        self.vars.retain(|v, _| live.contains(v));
+    }
 
-        match self.solver.check() {
+    /// Check under the given indicator literals, extracting a core on UNSAT.
+    fn check_guarded(&mut self, guards: &[Bool<'ctx>]) -> SatResult {
+        self.last_core = None;
+        match self.solver.check_assumptions(guards) {
             z3::SatResult::Sat => SatResult::Sat,
-            z3::SatResult::Unsat => SatResult::Unsat,
+            z3::SatResult::Unsat => {
+                self.last_core = Some(self.minimise_core(guards));
+                SatResult::Unsat
+            }
             z3::SatResult::Unknown => SatResult::Unknown,
         }
     }
 
-    /// Public entry point: add `delta` clauses then solve, reusing history if
-    /// `cfg(feature = "model-reuse")`.
-    pub fn unsat_recycle(&mut self, delta: Vec<Clause>) -> Result<SatResult, SatError> {
-        #[cfg(feature = "model-reuse")]
-        {
-            Ok(self.solve_incremental(&delta))
-        }
-        #[cfg(not(feature = "model-reuse"))]
-        {
-            // Naïve baseline: append delta into window then rebuild.
-            for cl in delta {
-                self.push_clause(cl);
+    /// Deletion-based minimisation of the solver's core, mapped back to
+    /// window indices (ascending).  A literal whose removal makes the check
+    /// SAT or UNKNOWN is kept, so the result is always a genuine core.
+    fn minimise_core(&self, guards: &[Bool<'ctx>]) -> Vec<usize> {
+        let mut core = self.solver.get_unsat_core();
+        let mut i = 0;
+        while i < core.len() {
+            let mut trial = core.clone();
+            trial.remove(i);
+            match self.solver.check_assumptions(&trial) {
+                z3::SatResult::Unsat => core = trial,
+                _ => i += 1,
             }
-            Ok(self.solve_naive())
         }
+        let mut idx: Vec<usize> = core
+            .iter()
+            .filter_map(|lit| guards.iter().position(|g| g == lit))
+            .collect();
+        idx.sort_unstable();
+        idx
     }
 
+    /// Naïve solve: rebuild entire solver from scratch.
+    fn solve_naive(&mut self) -> SatResult {
+        self.solver.reset();
+        if self.vars.len() > COMPACT_FACTOR * self.clauses.len().max(1) {
+            self.prune_vars();
+        }
+        self.assert_window();
+        let guards = self.live_guards();
+        self.check_guarded(&guards)
+    }
+
+    /// Incremental solve with model reuse: the solver already holds
+    /// `α → c` for every clause ever inserted, so only the live `α`s are
+    /// passed as assumptions.  Learned clauses survive across calls.
+    // Reference: Eén & Sörensson, "Temporal Induction by Incremental SAT Solving" (2003)
+    fn solve_incremental(&mut self) -> SatResult {
+        if self.retired > COMPACT_FACTOR * self.clauses.len().max(1) {
+            // Rebuild from the live window; retired clauses and their vars go.
+            self.solver.reset();
+            self.prune_vars();
+            self.assert_window();
+            self.retired = 0;
+        }
+        let guards = self.live_guards();
+        self.check_guarded(&guards)
+    }
+
+    /// Public entry point: add `delta` clauses then solve, reusing history
+    /// in `SolveMode::Incremental`.
+    pub fn unsat_recycle(&mut self, delta: Vec<Clause>) -> Result<SatResult, SatError> {
+        self.open_batch();
+        for cl in delta {
+            self.push_clause(cl);
+        }
+        Ok(match self.mode {
+            SolveMode::Naive => self.solve_naive(),
+            SolveMode::Incremental => self.solve_incremental(),
+        })
+    }
+
+    /// Minimised UNSAT core of the last `unsat_recycle` call as indices into
+    /// the clause window (0 = oldest surviving clause).  `None` unless the
+    /// last call returned `Unsat`.
     pub fn get_unsat_core(&self) -> Option<Vec<usize>> {
-        // TODO: Implement actual logic
-        None
+        self.last_core.clone()
+    }
+
+    /// Value of `lit` in the model of the last `unsat_recycle` that came
+    /// back `Sat`; `None` if there is no model or `lit` is unknown to it.
+    pub fn model_value(&self, lit: Lit) -> Option<bool> {
+        let var = self.vars.get(&lit.var)?;
+        let value = self.solver.get_model()?.eval(var, true)?.as_bool()?;
+        Some(value != lit.neg)
     }
 }
 
@@ -223,6 +394,77 @@ mod tests {
         let r2 = sat.unsat_recycle(vec![Clause(vec![Lit { var: 0, neg: true }])]).unwrap();
         assert!(matches!(r2, SatResult::Sat));
     }
+
+    #[test]
+    fn modes_agree_on_sliding_window() {
+        let ctx = ctx();
+        let steps = [
+            vec![Clause(vec![Lit { var: 0, neg: false }])],
+            vec![Clause(vec![Lit { var: 1, neg: false }])],
+            vec![Clause(vec![Lit { var: 0, neg: true }])], // clashes with step 1
+            vec![Clause(vec![Lit { var: 1, neg: true }])], // clashes with step 2
+            vec![Clause(vec![Lit { var: 2, neg: false }])],
+            vec![Clause(vec![Lit { var: 2, neg: false }])],
+        ];
+        let mut naive = SatCore::with_mode(&ctx, 2, SolveMode::Naive);
+        let mut inc = SatCore::with_mode(&ctx, 2, SolveMode::Incremental);
+        for step in steps {
+            let a = naive.unsat_recycle(step.clone()).unwrap();
+            let b = inc.unsat_recycle(step).unwrap();
+            assert_eq!(format!("{a:?}"), format!("{b:?}"));
+            assert_eq!(naive.get_unsat_core(), inc.get_unsat_core());
+            assert!(inc.len() <= 2);
+        }
+    }
+
+    #[test]
+    fn evicts_before_check() {
+        let ctx = ctx();
+        let mut sat = SatCore::with_mode(&ctx, 1, SolveMode::Incremental);
+        sat.unsat_recycle(vec![Clause(vec![Lit { var: 0, neg: false }])]).unwrap();
+        // Window is (¬p0) only – the evicted (p0) must not take part.
+        let res = sat.unsat_recycle(vec![Clause(vec![Lit { var: 0, neg: true }])]).unwrap();
+        assert!(matches!(res, SatResult::Sat));
+    }
+
+    #[test]
+    fn batch_window_ages_out_whole_ticks() {
+        let ctx = ctx();
+        for mode in [SolveMode::Naive, SolveMode::Incremental] {
+            let mut sat = SatCore::with_mode(&ctx, usize::MAX, mode).batch_window(3);
+            // tick 0: ⊥ ; ticks 1..: empty batches
+            let r = sat.unsat_recycle(vec![Clause(vec![])]).unwrap();
+            assert!(matches!(r, SatResult::Unsat));
+            for _ in 1..3 {
+                let r = sat.unsat_recycle(vec![]).unwrap();
+                assert!(matches!(r, SatResult::Unsat), "{mode:?}");
+            }
+            let r = sat.unsat_recycle(vec![]).unwrap();
+            assert!(matches!(r, SatResult::Sat), "{mode:?}");
+            assert!(sat.is_empty());
+        }
+    }
+
+    #[test]
+    fn minimal_core_indices() {
+        let ctx = ctx();
+        let mut sat = SatCore::new(&ctx, 10).batch_window(1);
+        // (p0) ∧ (p1) ∧ (¬p0) ⇒ core = {0, 2}
+        let res = sat
+            .unsat_recycle(vec![
+                Clause(vec![Lit { var: 0, neg: false }]),
+                Clause(vec![Lit { var: 1, neg: false }]),
+                Clause(vec![Lit { var: 0, neg: true }]),
+            ])
+            .unwrap();
+        assert!(matches!(res, SatResult::Unsat));
+        assert_eq!(sat.get_unsat_core(), Some(vec![0, 2]));
+        // Core is cleared once the window is SAT again: the next batch
+        // pushes the refuted one out.
+        let res = sat.unsat_recycle(vec![Clause(vec![Lit { var: 0, neg: false }])]).unwrap();
+        assert!(matches!(res, SatResult::Sat));
+        assert_eq!(sat.get_unsat_core(), None);
+    }
 }
 
 // =============================================================
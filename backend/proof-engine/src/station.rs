@@ -0,0 +1,359 @@
+// proof-engine/src/station.rs
+// =============================================================
+// Per-asset monitoring state.
+// -------------------------------------------------------------
+// * Samples are keyed by asset id (PLC, station, pipeline segment).  Each
+//   asset has its own window, reorder buffer, monitors and last published
+//   verdicts, so assets sharing a trace topic never share a trace and
+//   `|dX|` bounds compare consecutive samples of one asset.
+// * An asset runs the pack properties bound to its class
+//   (`PropertyPack::bound_to`); its station is created on first sight.
+// * Asset ids come off the wire, so the number of stations is capped
+//   (`max_assets`): a new asset beyond it retires the least recently seen
+//   one, which starts afresh if it shows up again.
+// * All stations borrow one solver `Context`.
+// * A station round-trips through a `checkpoint::Snapshot`, so a restart
+//   picks up the windows and published verdicts where they were.
+// =============================================================
+
+use crate::checkpoint::Snapshot;
+use crate::dsl::{Sample, Truth};
+use crate::engine::{Engine, EngineMode};
+use crate::pack::PropertyPack;
+use crate::reorder::ReorderBuffer;
+use std::collections::HashMap;
+use z3::Context;
+
+pub struct Station<'ctx> {
+    /// Pack indices of the properties this asset runs, in engine order.
+    pub bound: Vec<usize>,
+    pub engine: Engine<'ctx>,
+    pub reorder: ReorderBuffer<Sample>,
+    /// Last published verdict, parallel to `bound`.
+    pub prev: Vec<Truth>,
+    /// Last published margin alarm (`Property::alarm_margin`), parallel to
+    /// `bound`.
+    pub alarms: Vec<bool>,
+    /// `Stations` clock reading when the asset was last looked up.
+    seen: u64,
+}
+
+impl Station<'_> {
+    /// Snapshot of this station, the one of `asset`, under `pack`.
+    pub fn snapshot(&self, asset: &str, pack: &PropertyPack) -> Snapshot {
+        let (window, stamps) = (self.engine.window(), self.engine.timestamps());
+        Snapshot {
+            asset: asset.to_string(),
+            pack_version: pack.version.clone(),
+            window: stamps.iter().copied().zip(window.iter().cloned()).rev().collect(),
+            pending: self.reorder.pending_items().map(|(ts, s)| (ts, s.clone())).collect(),
+            newest: self.reorder.newest(),
+            released: self.reorder.released(),
+            verdicts: self
+                .bound
+                .iter()
+                .zip(&self.prev)
+                .map(|(&i, &truth)| (pack.properties[i].id.clone(), truth))
+                .collect(),
+            alarms: self
+                .bound
+                .iter()
+                .zip(&self.alarms)
+                .filter(|(_, &alarm)| alarm)
+                .map(|(&i, _)| pack.properties[i].id.clone())
+                .collect(),
+            history: self
+                .bound
+                .iter()
+                .zip(self.engine.histories())
+                .map(|(&i, history)| (pack.properties[i].id.clone(), history))
+                .collect(),
+        }
+    }
+}
+
+pub struct Stations<'ctx> {
+    ctx: &'ctx Context,
+    mode: EngineMode,
+    lateness_ns: i64,
+    stations: HashMap<String, Station<'ctx>>,
+    max_assets: usize,
+    /// Bumped on every `entry`, to find the least recently seen asset.
+    clock: u64,
+}
+
+impl<'ctx> Stations<'ctx> {
+    pub fn new(ctx: &'ctx Context, mode: EngineMode, allowed_lateness_ns: i64) -> Self {
+        Stations {
+            ctx,
+            mode,
+            lateness_ns: allowed_lateness_ns,
+            stations: HashMap::new(),
+            max_assets: usize::MAX,
+            clock: 0,
+        }
+    }
+
+    /// Keep at most `n` stations (see `make_room`); unbounded by default.
+    pub fn max_assets(mut self, n: usize) -> Self {
+        self.max_assets = n.max(1);
+        self
+    }
+
+    /// Before `entry` sets up `asset`: if that would exceed `max_assets`,
+    /// take out the least recently seen station and hand it back, so the
+    /// caller can judge what its reorder buffer still holds.
+    pub fn make_room(&mut self, asset: &str) -> Option<(String, Station<'ctx>)> {
+        if self.stations.len() < self.max_assets || self.stations.contains_key(asset) {
+            return None;
+        }
+        let oldest = self.stations.iter().min_by_key(|(_, s)| s.seen).map(|(a, _)| a.clone())?;
+        self.stations.remove_entry(&oldest)
+    }
+
+    /// Number of assets seen so far.
+    pub fn len(&self) -> usize {
+        self.stations.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.stations.is_empty()
+    }
+
+    pub fn assets(&self) -> std::collections::hash_map::Keys<'_, String, Station<'ctx>> {
+        self.stations.keys()
+    }
+
+    pub fn get(&self, asset: &str) -> Option<&Station<'ctx>> {
+        self.stations.get(asset)
+    }
+
+    /// The station of `asset`, set up with the properties `pack` binds to
+    /// it if the asset is new.
+    pub fn entry(&mut self, asset: &str, pack: &PropertyPack) -> &mut Station<'ctx> {
+        if !self.stations.contains_key(asset) {
+            let bound = pack.bound_to(asset);
+            let engine = Engine::new(self.ctx, self.mode, pack.monitored_at(&bound)).expect("packs validate horizons");
+            log::info!("asset {asset:?}: {} of {} properties", bound.len(), pack.properties.len());
+            let station = Station {
+                prev: vec![Truth::True; bound.len()],
+                alarms: vec![false; bound.len()],
+                bound,
+                engine,
+                reorder: ReorderBuffer::new(self.lateness_ns),
+                seen: 0,
+            };
+            self.stations.insert(asset.to_string(), station);
+        }
+        self.clock += 1;
+        let station = self.stations.get_mut(asset).expect("inserted above");
+        station.seen = self.clock;
+        station
+    }
+
+    /// Snapshot of `asset`'s station, if the asset has been seen.
+    pub fn snapshot(&self, asset: &str, pack: &PropertyPack) -> Option<Snapshot> {
+        Some(self.stations.get(asset)?.snapshot(asset, pack))
+    }
+
+    /// Rebuild a station from `snapshot`, replacing any the asset has.  The
+    /// window is refilled; monitor histories and verdicts carry over by
+    /// property id if the snapshot was taken under the same pack version,
+    /// else monitors start afresh on the window.
+    pub fn restore(&mut self, snapshot: Snapshot, pack: &PropertyPack) {
+        let Snapshot { asset, pack_version, window, pending, newest, released, verdicts, alarms, mut history } =
+            snapshot;
+        self.stations.remove(&asset);
+        let lateness_ns = self.lateness_ns;
+        let station = self.entry(&asset, pack);
+        let same = pack_version == pack.version;
+        if !same {
+            log::warn!("asset {asset:?}: checkpoint is for pack {pack_version}, not {}; verdicts reset", pack.version);
+        }
+        let histories: Vec<Option<Vec<Truth>>> = station
+            .bound
+            .iter()
+            .map(|&i| history.remove(&pack.properties[i].id).filter(|_| same))
+            .collect();
+        station.engine.resume(window, &histories);
+        station.reorder = ReorderBuffer::resume(lateness_ns, newest, released, pending);
+        if same {
+            for (k, &i) in station.bound.iter().enumerate() {
+                let id = &pack.properties[i].id;
+                if let Some(&truth) = verdicts.get(id) {
+                    station.prev[k] = truth;
+                }
+                station.alarms[k] = alarms.contains(id);
+            }
+        }
+    }
+
+    /// Rebind every station from `pack` to `next`.  Windows survive, and
+    /// monitor and verdict state carry over for unchanged rules.
+    pub fn reload(&mut self, pack: &PropertyPack, next: &PropertyPack) {
+        for (asset, station) in &mut self.stations {
+            let bound = next.bound_to(asset);
+            let kept: Vec<Option<usize>> = bound
+                .iter()
+                .map(|&j| {
+                    let p = &next.properties[j];
+                    pack.index_of(&p.id)
+                        .filter(|&i| pack.properties[i].same_rule(p))
+                        .and_then(|i| station.bound.iter().position(|&b| b == i))
+                })
+                .collect();
+            station.prev = kept.iter().map(|k| k.map_or(Truth::True, |k| station.prev[k])).collect();
+            station.alarms = kept.iter().map(|k| k.is_some_and(|k| station.alarms[k])).collect();
+            station.engine.reload(next.monitored_at(&bound)).expect("packs validate horizons");
+            station.bound = bound;
+        }
+    }
+}
+
+// ---------------------------
+// Unit tests
+// ---------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dsl::Var;
+    use crate::monitor::solver_context;
+
+    const PACK: &str = r#"
+        version = "t-1"
+
+        [assets]
+        seg7 = "segment"
+
+        [[property]]
+        id = "ramp"
+        severity = "warning"
+        asset_class = "segment"
+        expr = "|dP| <= 1"
+
+        [[property]]
+        id = "max_pressure"
+        severity = "critical"
+        expr = "P <= 120"
+    "#;
+
+    /// Feed one seg7 sample the way the binary does; returns the verdict
+    /// changes it would publish.
+    fn feed(stations: &mut Stations<'_>, pack: &PropertyPack, secs: i64, p: f64) -> Vec<(String, Truth)> {
+        let station = stations.entry("seg7", pack);
+        let mut out = Vec::new();
+        for (ts_ns, sample) in station.reorder.push(secs * 1_000_000_000, HashMap::from([(Var::P, p)])).unwrap() {
+            for (k, v) in station.engine.tick(ts_ns, sample).into_iter().enumerate() {
+                if v.truth != station.prev[k] {
+                    station.prev[k] = v.truth;
+                    out.push((pack.properties[station.bound[k]].id.clone(), v.truth));
+                }
+            }
+        }
+        out
+    }
+
+    #[test]
+    fn restart_recovers_windows_and_verdicts() {
+        let ctx = solver_context();
+        let pack = PropertyPack::from_str(PACK, 3).unwrap();
+        let trace = [50.0, 51.0, 130.0, 131.0, 132.0, 119.0, 118.0, 117.0];
+        let run = |stations: &mut Stations<'_>, from: usize, to: usize| {
+            (from..to).flat_map(|i| feed(stations, &pack, i as i64, trace[i])).collect::<Vec<_>>()
+        };
+        let stations = || Stations::new(&ctx, EngineMode::Sat, 1_000_000_000);
+        let expected = run(&mut stations(), 0, trace.len());
+
+        let mut before = stations();
+        let published = run(&mut before, 0, 4);
+        assert!(published.contains(&("max_pressure".into(), Truth::False)));
+        let bytes = before.snapshot("seg7", &pack).unwrap().encode();
+        drop(before);
+
+        // The restarted engine neither repeats the FAIL nor loses history
+        let mut after = stations();
+        after.restore(Snapshot::decode(&bytes).unwrap(), &pack);
+        assert_eq!(after.get("seg7").unwrap().reorder.pending(), 1);
+        assert_eq!([published, run(&mut after, 4, trace.len())].concat(), expected);
+    }
+
+    #[test]
+    fn restart_keeps_history_older_than_the_window() {
+        let ctx = solver_context();
+        let pack = PropertyPack::from_str(
+            r#"
+            version = "t-1"
+
+            [[property]]
+            id = "calm"
+            severity = "critical"
+            expr = "always[2](P <= 120)"
+            "#,
+            3,
+        )
+        .unwrap();
+        // The t=1 peak fails ticks 1-3, held until tick 5; the window only
+        // keeps 3 samples, so a replay after tick 4 would pass at once
+        let trace = [100.0, 130.0, 100.0, 100.0, 100.0, 100.0, 100.0, 100.0];
+        for mode in [EngineMode::Sat, EngineMode::Rust] {
+            let run = |stations: &mut Stations<'_>, from: usize, to: usize| {
+                (from..to).map(|i| feed(stations, &pack, i as i64, trace[i])).collect::<Vec<_>>()
+            };
+            let whole = run(&mut Stations::new(&ctx, mode, 0), 0, trace.len());
+            assert_eq!(whole[6], [("calm".to_string(), Truth::True)], "{mode}");
+
+            let mut before = Stations::new(&ctx, mode, 0);
+            let published = run(&mut before, 0, 5);
+            let bytes = before.snapshot("seg7", &pack).unwrap().encode();
+            let mut after = Stations::new(&ctx, mode, 0);
+            after.restore(Snapshot::decode(&bytes).unwrap(), &pack);
+            assert_eq!([published, run(&mut after, 5, trace.len())].concat(), whole, "{mode}");
+        }
+    }
+
+    #[test]
+    fn retires_the_least_recently_seen_asset() {
+        let ctx = solver_context();
+        let pack = PropertyPack::from_str(PACK, 2).unwrap();
+        let mut stations = Stations::new(&ctx, EngineMode::Rust, 0).max_assets(2);
+        for asset in ["seg7", "seg8", "seg7"] {
+            assert!(stations.make_room(asset).is_none());
+            stations.entry(asset, &pack);
+        }
+        assert!(stations.make_room("seg8").is_none());
+        let (retired, station) = stations.make_room("cs2").unwrap();
+        assert_eq!(retired, "seg8");
+        assert_eq!(station.snapshot(&retired, &pack).asset, "seg8");
+        stations.entry("cs2", &pack);
+        assert_eq!(stations.len(), 2);
+        assert!(stations.get("seg8").is_none());
+    }
+
+    #[test]
+    fn assets_keep_separate_traces() {
+        let ctx = solver_context();
+        let pack = PropertyPack::from_str(PACK, 2).unwrap();
+        let mut stations = Stations::new(&ctx, EngineMode::Rust, 0);
+        // Interleaved on one topic, each asset ramps by 1 bar/s
+        let samples = [("seg7", 0, 50.0), ("seg8", 0, 100.0), ("seg7", 1, 51.0), ("seg8", 1, 101.0)];
+        let mut last = HashMap::new();
+        for (asset, secs, p) in samples {
+            let station = stations.entry(asset, &pack);
+            let v = station.engine.tick(secs * 1_000_000_000, HashMap::from([(Var::P, p)]));
+            last.insert(asset, v.iter().map(|v| v.truth).collect::<Vec<_>>());
+        }
+        assert_eq!(stations.len(), 2);
+        assert_eq!(last["seg7"], [Truth::True, Truth::True]);
+        assert_eq!(last["seg8"], [Truth::True]);
+        assert_eq!(stations.get("seg8").unwrap().bound, [1]);
+
+        // Unbinding `ramp` starts it afresh on seg8; `max_pressure` carries over
+        stations.entry("seg8", &pack).prev[0] = Truth::False;
+        let next = PropertyPack::from_str(&PACK.replace("asset_class = \"segment\"\n", ""), 2).unwrap();
+        stations.reload(&pack, &next);
+        let seg8 = stations.get("seg8").unwrap();
+        assert_eq!(seg8.bound, [0, 1]);
+        assert_eq!(seg8.prev, [Truth::True, Truth::False]);
+        assert_eq!(seg8.engine.window().len(), 2);
+    }
+}
@@ -0,0 +1,265 @@
+//! Interned PLC tag namespace: tag name ↔ compact `dsl::Var` id.
+//!
+//! One process-wide registry is shared by the trace parser, `Sample`s, the
+//! trace hash and the encoders.  It starts with the built-in tags
+//! `P, T, Flow, Valve` (ids 0‥3, see the `Var` constants) and is either
+//! *open* – every tag seen on the wire is interned, and logged the first
+//! time – or, once loaded from a config file, *closed*: unknown tags are
+//! reported and left out of samples.  Every lookup by name (`resolve`,
+//! `lookup_or_intern`, `Var::named`) obeys the same rule.
+
+use crate::dsl::Var;
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, RwLock};
+use thiserror::Error;
+
+/// Built-in tags, in id order.
+pub const BUILTIN: [&str; 4] = ["P", "T", "Flow", "Valve"];
+
+#[derive(Error, Debug)]
+pub enum TagError {
+    #[error("cannot read tag registry {path}: {source}")]
+    Io { path: String, source: std::io::Error },
+    #[error("line {line}: invalid tag name {name:?}")]
+    InvalidName { line: usize, name: String },
+}
+
+/// Outcome of resolving a tag name seen in a trace message.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Resolved {
+    Known(Var),
+    /// Not registered and the registry is closed; `first` is set the first
+    /// time this name shows up.
+    Unknown { first: bool },
+}
+
+#[derive(Debug)]
+pub struct TagRegistry {
+    names: Vec<Arc<str>>,
+    ids: HashMap<Arc<str>, Var>,
+    closed: bool,
+    unknown: HashMap<String, u64>,
+}
+
+impl Default for TagRegistry {
+    fn default() -> Self {
+        let mut reg = TagRegistry {
+            names: Vec::new(),
+            ids: HashMap::new(),
+            closed: false,
+            unknown: HashMap::new(),
+        };
+        for name in BUILTIN {
+            reg.intern(name);
+        }
+        reg
+    }
+}
+
+impl TagRegistry {
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.closed
+    }
+
+    /// Id of `name`, registering it if needed.
+    pub fn intern(&mut self, name: &str) -> Var {
+        if let Some(&v) = self.ids.get(name) {
+            return v;
+        }
+        let v = Var(self.names.len() as u32);
+        let name: Arc<str> = Arc::from(name);
+        self.names.push(name.clone());
+        self.ids.insert(name, v);
+        v
+    }
+
+    pub fn get(&self, name: &str) -> Option<Var> {
+        self.ids.get(name).copied()
+    }
+
+    pub fn name(&self, v: Var) -> Option<&Arc<str>> {
+        self.names.get(v.0 as usize)
+    }
+
+    /// Register every tag listed in `src` (one per line, `#` comments) and
+    /// close the registry.  Returns the number of tags listed.
+    pub fn load_str(&mut self, src: &str) -> Result<usize, TagError> {
+        let mut n = 0;
+        for (i, line) in src.lines().enumerate() {
+            let name = line.split('#').next().unwrap_or("").trim();
+            if name.is_empty() {
+                continue;
+            }
+            if !valid_name(name) {
+                return Err(TagError::InvalidName { line: i + 1, name: name.into() });
+            }
+            self.intern(name);
+            n += 1;
+        }
+        self.closed = true;
+        Ok(n)
+    }
+
+    /// Resolve a tag seen on the wire (interning it while the registry is open).
+    pub fn resolve(&mut self, name: &str) -> Resolved {
+        if let Some(v) = self.get(name) {
+            return Resolved::Known(v);
+        }
+        if !self.closed && valid_name(name) {
+            return Resolved::Known(self.intern(name));
+        }
+        let count = self.unknown.entry(name.to_string()).or_insert(0);
+        *count += 1;
+        Resolved::Unknown { first: *count == 1 }
+    }
+
+    /// Tag named in a property: known tags, or new ones while the registry is
+    /// open.  Unlike `resolve`, misses are not counted as wire anomalies.
+    pub fn lookup_or_intern(&mut self, name: &str) -> Option<Var> {
+        match self.get(name) {
+            Some(v) => Some(v),
+            None if !self.closed && valid_name(name) => Some(self.intern(name)),
+            None => None,
+        }
+    }
+
+    /// Unknown tags seen so far with their occurrence counts.
+    pub fn unknown(&self) -> Vec<(String, u64)> {
+        let mut v: Vec<(String, u64)> = self.unknown.iter().map(|(k, c)| (k.clone(), *c)).collect();
+        v.sort();
+        v
+    }
+}
+
+/// Tag names: non-empty, ASCII alphanumerics plus `_ . : -`, not starting
+/// with a digit (so the property parser can tell them from numbers) nor
+/// ending in `-` (which the parser would read as the start of `->`).
+pub fn valid_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | ':' | '-'))
+        && !name.ends_with('-')
+}
+
+static REGISTRY: Lazy<RwLock<TagRegistry>> = Lazy::new(|| RwLock::new(TagRegistry::default()));
+
+/// Load the process-wide registry from a tag list file and close it.
+pub fn load_config(path: impl AsRef<Path>) -> Result<usize, TagError> {
+    let path = path.as_ref();
+    let src = std::fs::read_to_string(path)
+        .map_err(|source| TagError::Io { path: path.display().to_string(), source })?;
+    REGISTRY.write().unwrap().load_str(&src)
+}
+
+pub fn intern(name: &str) -> Var {
+    if let Some(v) = lookup(name) {
+        return v;
+    }
+    REGISTRY.write().unwrap().intern(name)
+}
+
+pub fn lookup(name: &str) -> Option<Var> {
+    REGISTRY.read().unwrap().get(name)
+}
+
+/// Resolve a tag from a trace message; unknown tags are logged once.
+pub fn resolve(name: &str) -> Option<Var> {
+    if let Some(v) = lookup(name) {
+        return Some(v);
+    }
+    let mut reg = REGISTRY.write().unwrap();
+    let before = reg.len();
+    match reg.resolve(name) {
+        Resolved::Known(v) => {
+            if reg.len() > before {
+                log::info!("new tag {name:?} interned (registry is open)");
+            }
+            Some(v)
+        }
+        Resolved::Unknown { first } => {
+            if first {
+                log::warn!("unknown tag {name:?} not in registry – ignored");
+            }
+            None
+        }
+    }
+}
+
+pub fn lookup_or_intern(name: &str) -> Option<Var> {
+    if let Some(v) = lookup(name) {
+        return Some(v);
+    }
+    REGISTRY.write().unwrap().lookup_or_intern(name)
+}
+
+pub fn name_of(v: Var) -> Arc<str> {
+    REGISTRY
+        .read()
+        .unwrap()
+        .name(v)
+        .cloned()
+        .unwrap_or_else(|| Arc::from(format!("#{}", v.0)))
+}
+
+pub fn unknown_tags() -> Vec<(String, u64)> {
+    REGISTRY.read().unwrap().unknown()
+}
+
+// ---------------------------
+// Unit tests
+// ---------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builtins_match_var_constants() {
+        let reg = TagRegistry::default();
+        assert_eq!(reg.get("P"), Some(Var::P));
+        assert_eq!(reg.get("T"), Some(Var::T));
+        assert_eq!(reg.get("Flow"), Some(Var::Flow));
+        assert_eq!(reg.get("Valve"), Some(Var::Valve));
+        assert_eq!(name_of(Var::Flow).as_ref(), "Flow");
+    }
+
+    #[test]
+    fn open_registry_interns_new_tags() {
+        let mut reg = TagRegistry::default();
+        let a = reg.intern("Seg7.P_in");
+        assert_eq!(reg.intern("Seg7.P_in"), a);
+        assert_eq!(reg.resolve("Seg7.T_out"), Resolved::Known(Var(5)));
+        assert_eq!(reg.len(), 6);
+    }
+
+    #[test]
+    fn closed_registry_reports_unknown() {
+        let mut reg = TagRegistry::default();
+        let n = reg.load_str("# station 7\nSeg7.P_in\n\nSeg7.T_out  # outlet\n").unwrap();
+        assert_eq!(n, 2);
+        assert!(reg.is_closed());
+        assert!(matches!(reg.resolve("Seg7.T_out"), Resolved::Known(_)));
+        assert_eq!(reg.resolve("Bogus"), Resolved::Unknown { first: true });
+        assert_eq!(reg.resolve("Bogus"), Resolved::Unknown { first: false });
+        assert_eq!(reg.unknown(), vec![("Bogus".to_string(), 2)]);
+        // names in properties and checkpoints obey the same closed list
+        assert_eq!(reg.lookup_or_intern("Bogus"), None);
+        assert_eq!(reg.len(), 6);
+    }
+
+    #[test]
+    fn rejects_invalid_names() {
+        let err = TagRegistry::default().load_str("P\n9lives\n").unwrap_err();
+        assert!(matches!(err, TagError::InvalidName { line: 2, .. }));
+        assert!(!valid_name("Seg-") && valid_name("Seg-7"));
+    }
+}
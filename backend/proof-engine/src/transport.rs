@@ -0,0 +1,565 @@
+// proof-engine/src/transport.rs
+// =============================================================
+// Where traces come from and where proofs go.
+// -------------------------------------------------------------
+// * `TraceSource` yields raw trace messages; `ProofSink` takes what a run
+//   of messages produced (proof packets, checkpoints) as a `Batch`, with
+//   the consumption to `Commit` alongside it.
+// * Backends: Kafka (transactional, exactly-once), JSONL files,
+//   stdin / stdout, and in-memory ones for tests.
+// * `Endpoint` names a backend in config: `kafka`, `stdio`, or
+//   `file:<path>`.
+// * Only the Kafka and memory sinks keep checkpoints; pipelines on the
+//   other backends start from empty windows.
+// =============================================================
+
+use crate::checkpoint::Snapshot;
+use rdkafka::admin::{AdminClient, AdminOptions, NewTopic, TopicReplication};
+use rdkafka::client::DefaultClientContext;
+use rdkafka::config::ClientConfig;
+use rdkafka::consumer::{BaseConsumer, Consumer, ConsumerGroupMetadata, StreamConsumer};
+use rdkafka::error::{KafkaError, RDKafkaErrorCode};
+use rdkafka::producer::{FutureProducer, FutureRecord, Producer};
+use rdkafka::{Message, Offset, TopicPartitionList};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::future::Future;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader, Lines};
+use tokio::sync::mpsc;
+
+/// One raw trace message.
+pub struct TraceMsg {
+    /// Asset named by the transport (the Kafka key), if any.
+    pub key: Option<String>,
+    pub payload: Vec<u8>,
+    /// Where it was read, for sources whose consumption is committed.
+    pub position: Option<Position>,
+}
+
+impl TraceMsg {
+    pub fn new(payload: impl Into<Vec<u8>>) -> Self {
+        TraceMsg { key: None, payload: payload.into(), position: None }
+    }
+}
+
+/// A message's place in a partitioned log.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Position {
+    pub topic: String,
+    pub partition: i32,
+    pub offset: i64,
+}
+
+/// Consumption a sink may commit together with its output.
+#[derive(Default)]
+pub struct Commit {
+    /// Offset each (topic, partition) resumes from.
+    pub resume: BTreeMap<(String, i32), i64>,
+    /// The consumer group committing, from `TraceSource::group`.
+    pub group: Option<ConsumerGroupMetadata>,
+}
+
+/// Output of one transaction, published atomically by sinks that can.
+#[derive(Debug, Default)]
+pub struct Batch {
+    /// Proof packets and events, serialized.
+    pub packets: Vec<Vec<u8>>,
+    pub checkpoints: Vec<Snapshot>,
+    /// Assets whose checkpoints are deleted (tombstoned on Kafka).
+    pub retired: Vec<String>,
+}
+
+impl Batch {
+    /// Add `snapshot`, replacing an older one of the same asset.
+    pub fn checkpoint(&mut self, snapshot: Snapshot) {
+        self.checkpoints.retain(|s| s.asset != snapshot.asset);
+        self.retired.retain(|a| *a != snapshot.asset);
+        self.checkpoints.push(snapshot);
+    }
+
+    /// Delete `asset`'s checkpoint, along with any added to this batch.
+    pub fn retire(&mut self, asset: &str) {
+        self.checkpoints.retain(|s| s.asset != asset);
+        if !self.retired.iter().any(|a| a == asset) {
+            self.retired.push(asset.to_string());
+        }
+    }
+}
+
+pub trait TraceSource {
+    /// Next message; `None` once the source is exhausted.
+    fn next(&mut self) -> impl Future<Output = anyhow::Result<Option<TraceMsg>>>;
+
+    /// Consumer group that commits this source's positions, if any.
+    fn group(&self) -> Option<ConsumerGroupMetadata> {
+        None
+    }
+}
+
+pub trait ProofSink {
+    /// Publish `batch`, committing `commit` with it where the sink can.
+    fn send(&mut self, batch: Batch, commit: Commit) -> impl Future<Output = anyhow::Result<()>>;
+
+    /// Whether batches should carry checkpoints.
+    fn keeps_checkpoints(&self) -> bool {
+        false
+    }
+
+    /// Latest checkpoint per asset.
+    fn restore(&mut self) -> impl Future<Output = anyhow::Result<Vec<Snapshot>>> {
+        async { Ok(Vec::new()) }
+    }
+}
+
+/// A backend as named in config.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Endpoint {
+    Kafka,
+    /// stdin for sources, stdout for sinks.
+    Stdio,
+    /// A JSONL file, read or appended to.
+    File(PathBuf),
+}
+
+impl FromStr for Endpoint {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "kafka" => Ok(Endpoint::Kafka),
+            "stdio" | "-" => Ok(Endpoint::Stdio),
+            _ => match s.strip_prefix("file:") {
+                Some(path) if !path.is_empty() => Ok(Endpoint::File(path.into())),
+                _ => Err(format!("unknown endpoint {s:?} (expected `kafka`, `stdio` or `file:<path>`)")),
+            },
+        }
+    }
+}
+
+impl fmt::Display for Endpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Endpoint::Kafka => f.write_str("kafka"),
+            Endpoint::Stdio => f.write_str("stdio"),
+            Endpoint::File(path) => write!(f, "file:{}", path.display()),
+        }
+    }
+}
+
+/// Kafka settings shared by source and sink.
+#[derive(Clone, Debug)]
+pub struct KafkaConfig {
+    pub brokers: String,
+    pub trace_topic: String,
+    pub proof_topic: String,
+    pub group_id: String,
+    /// Unique per running instance.
+    pub transactional_id: String,
+    /// Compacted topic for checkpoints; `None` disables them.
+    pub checkpoint_topic: Option<String>,
+}
+
+impl Endpoint {
+    pub async fn source(&self, kafka: &KafkaConfig) -> anyhow::Result<Source> {
+        Ok(match self {
+            Endpoint::Kafka => Source::Kafka(KafkaSource::new(kafka)?),
+            Endpoint::Stdio => Source::Jsonl(JsonlSource::stdin()),
+            Endpoint::File(path) => Source::Jsonl(JsonlSource::open(path).await?),
+        })
+    }
+
+    pub async fn sink(&self, kafka: &KafkaConfig) -> anyhow::Result<Sink> {
+        Ok(match self {
+            Endpoint::Kafka => Sink::Kafka(KafkaSink::connect(kafka).await?),
+            Endpoint::Stdio => Sink::Jsonl(JsonlSink::stdout()),
+            Endpoint::File(path) => Sink::Jsonl(JsonlSink::append(path).await?),
+        })
+    }
+}
+
+/// Any source backend, chosen at run time.
+pub enum Source {
+    Kafka(KafkaSource),
+    Jsonl(JsonlSource),
+    Memory(MemorySource),
+}
+
+impl TraceSource for Source {
+    async fn next(&mut self) -> anyhow::Result<Option<TraceMsg>> {
+        match self {
+            Source::Kafka(s) => s.next().await,
+            Source::Jsonl(s) => s.next().await,
+            Source::Memory(s) => s.next().await,
+        }
+    }
+
+    fn group(&self) -> Option<ConsumerGroupMetadata> {
+        match self {
+            Source::Kafka(s) => s.group(),
+            Source::Jsonl(s) => s.group(),
+            Source::Memory(s) => s.group(),
+        }
+    }
+}
+
+/// Any sink backend, chosen at run time.
+pub enum Sink {
+    Kafka(KafkaSink),
+    Jsonl(JsonlSink),
+    Memory(MemorySink),
+}
+
+impl ProofSink for Sink {
+    async fn send(&mut self, batch: Batch, commit: Commit) -> anyhow::Result<()> {
+        match self {
+            Sink::Kafka(s) => s.send(batch, commit).await,
+            Sink::Jsonl(s) => s.send(batch, commit).await,
+            Sink::Memory(s) => s.send(batch, commit).await,
+        }
+    }
+
+    fn keeps_checkpoints(&self) -> bool {
+        match self {
+            Sink::Kafka(s) => s.keeps_checkpoints(),
+            Sink::Jsonl(s) => s.keeps_checkpoints(),
+            Sink::Memory(s) => s.keeps_checkpoints(),
+        }
+    }
+
+    async fn restore(&mut self) -> anyhow::Result<Vec<Snapshot>> {
+        match self {
+            Sink::Kafka(s) => s.restore().await,
+            Sink::Jsonl(s) => s.restore().await,
+            Sink::Memory(s) => s.restore().await,
+        }
+    }
+}
+
+// ------------------------------------------------------------------
+// Kafka
+// ------------------------------------------------------------------
+
+/// Bound on each transaction step (offset hand-over, commit, abort).
+const TXN_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Trace topic consumer.  Offsets are never committed automatically:
+/// `KafkaSink` commits them inside its transactions.
+pub struct KafkaSource {
+    consumer: StreamConsumer,
+}
+
+impl KafkaSource {
+    pub fn new(cfg: &KafkaConfig) -> anyhow::Result<Self> {
+        let consumer: StreamConsumer = ClientConfig::new()
+            .set("bootstrap.servers", &cfg.brokers)
+            .set("group.id", &cfg.group_id)
+            .set("enable.auto.commit", "false")
+            .set("isolation.level", "read_committed")
+            .create()?;
+        consumer.subscribe(&[&cfg.trace_topic])?;
+        Ok(KafkaSource { consumer })
+    }
+}
+
+impl TraceSource for KafkaSource {
+    async fn next(&mut self) -> anyhow::Result<Option<TraceMsg>> {
+        let msg = self.consumer.recv().await?;
+        Ok(Some(TraceMsg {
+            key: msg.key().and_then(|k| std::str::from_utf8(k).ok()).map(str::to_string),
+            payload: msg.payload().unwrap_or_default().to_vec(),
+            position: Some(Position { topic: msg.topic().to_string(), partition: msg.partition(), offset: msg.offset() }),
+        }))
+    }
+
+    fn group(&self) -> Option<ConsumerGroupMetadata> {
+        self.consumer.group_metadata()
+    }
+}
+
+/// Transactional proof producer: a batch, its checkpoints and the consumer
+/// offsets of the messages behind it commit in one Kafka transaction, so
+/// `read_committed` readers see each verdict change exactly once.
+pub struct KafkaSink {
+    producer: FutureProducer,
+    brokers: String,
+    proof_topic: String,
+    checkpoint_topic: Option<String>,
+}
+
+impl KafkaSink {
+    pub async fn connect(cfg: &KafkaConfig) -> anyhow::Result<Self> {
+        let producer: FutureProducer = ClientConfig::new()
+            .set("bootstrap.servers", &cfg.brokers)
+            .set("transactional.id", &cfg.transactional_id)
+            .set("enable.idempotence", "true")
+            .set("acks", "all")
+            .create()?;
+        // Fences any older instance with the same transactional id
+        tokio::task::block_in_place(|| producer.init_transactions(TXN_TIMEOUT))?;
+        if let Some(topic) = &cfg.checkpoint_topic {
+            ensure_compacted(&cfg.brokers, topic).await?;
+        }
+        Ok(KafkaSink {
+            producer,
+            brokers: cfg.brokers.clone(),
+            proof_topic: cfg.proof_topic.clone(),
+            checkpoint_topic: cfg.checkpoint_topic.clone(),
+        })
+    }
+
+    async fn publish(&self, topic: &str, key: Option<&str>, payload: &[u8]) -> anyhow::Result<()> {
+        let mut record = FutureRecord::to(topic).payload(payload);
+        if let Some(key) = key {
+            record = record.key(key);
+        }
+        self.producer.send(record, Duration::from_secs(0)).await.map_err(|(e, _)| e)?;
+        Ok(())
+    }
+
+    /// Null payload under `key`: compaction drops the key's records.
+    async fn tombstone(&self, topic: &str, key: &str) -> anyhow::Result<()> {
+        let record = FutureRecord::<str, [u8]>::to(topic).key(key);
+        self.producer.send(record, Duration::from_secs(0)).await.map_err(|(e, _)| e)?;
+        Ok(())
+    }
+}
+
+impl ProofSink for KafkaSink {
+    async fn send(&mut self, batch: Batch, commit: Commit) -> anyhow::Result<()> {
+        self.producer.begin_transaction()?;
+        let sent = async {
+            for packet in &batch.packets {
+                self.publish(&self.proof_topic, None, packet).await?;
+            }
+            if let Some(topic) = &self.checkpoint_topic {
+                for snapshot in &batch.checkpoints {
+                    self.publish(topic, Some(&snapshot.asset), &snapshot.encode()).await?;
+                }
+                for asset in &batch.retired {
+                    self.tombstone(topic, asset).await?;
+                }
+            }
+            tokio::task::block_in_place(|| {
+                if let Some(group) = commit.group.as_ref().filter(|_| !commit.resume.is_empty()) {
+                    let mut offsets = TopicPartitionList::new();
+                    for ((topic, partition), &offset) in &commit.resume {
+                        offsets.add_partition_offset(topic, *partition, Offset::Offset(offset))?;
+                    }
+                    self.producer.send_offsets_to_transaction(&offsets, group, TXN_TIMEOUT)?;
+                }
+                self.producer.commit_transaction(TXN_TIMEOUT)?;
+                anyhow::Ok(())
+            })
+        }
+        .await;
+        if let Err(e) = sent {
+            // Nothing of the batch becomes visible
+            tokio::task::block_in_place(|| self.producer.abort_transaction(TXN_TIMEOUT))
+                .map_err(|abort| anyhow::anyhow!("{e}; abort failed: {abort}"))?;
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    fn keeps_checkpoints(&self) -> bool {
+        self.checkpoint_topic.is_some()
+    }
+
+    async fn restore(&mut self) -> anyhow::Result<Vec<Snapshot>> {
+        match &self.checkpoint_topic {
+            Some(topic) => {
+                let (brokers, topic) = (self.brokers.clone(), topic.clone());
+                tokio::task::block_in_place(|| load_checkpoints(&brokers, &topic))
+            }
+            None => Ok(Vec::new()),
+        }
+    }
+}
+
+/// Create `topic`, compacted, unless it exists.  One partition, one
+/// replica: create it beforehand for anything else.
+async fn ensure_compacted(brokers: &str, topic: &str) -> anyhow::Result<()> {
+    let admin: AdminClient<DefaultClientContext> = ClientConfig::new().set("bootstrap.servers", brokers).create()?;
+    let spec = NewTopic::new(topic, 1, TopicReplication::Fixed(1)).set("cleanup.policy", "compact");
+    for result in admin.create_topics([&spec], &AdminOptions::new()).await? {
+        match result {
+            Ok(_) | Err((_, RDKafkaErrorCode::TopicAlreadyExists)) => {}
+            Err((topic, code)) => anyhow::bail!("cannot create checkpoint topic {topic}: {code}"),
+        }
+    }
+    Ok(())
+}
+
+/// Latest committed snapshot per asset on the checkpoint topic; a
+/// tombstone deletes the asset's.  Snapshots that fail their checksum or
+/// format check are skipped.
+fn load_checkpoints(brokers: &str, topic: &str) -> anyhow::Result<Vec<Snapshot>> {
+    let consumer: BaseConsumer = ClientConfig::new()
+        .set("bootstrap.servers", brokers)
+        .set("group.id", "sentinel-monitor-restore")
+        .set("enable.auto.commit", "false")
+        .set("enable.partition.eof", "true")
+        .set("isolation.level", "read_committed")
+        .create()?;
+    let metadata = consumer.fetch_metadata(Some(topic), TXN_TIMEOUT)?;
+    let mut from_start = TopicPartitionList::new();
+    for p in metadata.topics().iter().flat_map(|t| t.partitions()) {
+        from_start.add_partition_offset(topic, p.id(), Offset::Beginning)?;
+    }
+    consumer.assign(&from_start)?;
+
+    let mut latest = HashMap::new();
+    let mut open = from_start.count();
+    while open > 0 {
+        match consumer.poll(TXN_TIMEOUT) {
+            None => anyhow::bail!("timed out reading checkpoints from {topic}"),
+            Some(Err(KafkaError::PartitionEOF(_))) => open -= 1,
+            Some(Err(e)) => return Err(e.into()),
+            Some(Ok(msg)) => match msg.payload().map(Snapshot::decode) {
+                Some(Ok(snapshot)) => {
+                    latest.insert(snapshot.asset.clone(), snapshot);
+                }
+                Some(Err(e)) => log::error!("skipping checkpoint {topic}/{}@{}: {e}", msg.partition(), msg.offset()),
+                None => {
+                    if let Some(asset) = msg.key().and_then(|k| std::str::from_utf8(k).ok()) {
+                        latest.remove(asset);
+                    }
+                }
+            },
+        }
+    }
+    Ok(latest.into_values().collect())
+}
+
+// ------------------------------------------------------------------
+// JSONL files and stdio
+// ------------------------------------------------------------------
+
+/// One trace message per line; blank lines are skipped.
+pub struct JsonlSource {
+    lines: Lines<BufReader<Box<dyn AsyncRead + Unpin + Send>>>,
+}
+
+impl JsonlSource {
+    pub fn new(reader: impl AsyncRead + Unpin + Send + 'static) -> Self {
+        let reader: Box<dyn AsyncRead + Unpin + Send> = Box::new(reader);
+        JsonlSource { lines: BufReader::new(reader).lines() }
+    }
+
+    pub fn stdin() -> Self {
+        Self::new(tokio::io::stdin())
+    }
+
+    pub async fn open(path: &std::path::Path) -> anyhow::Result<Self> {
+        let file = tokio::fs::File::open(path)
+            .await
+            .map_err(|e| anyhow::anyhow!("cannot open {}: {e}", path.display()))?;
+        Ok(Self::new(file))
+    }
+}
+
+impl TraceSource for JsonlSource {
+    async fn next(&mut self) -> anyhow::Result<Option<TraceMsg>> {
+        while let Some(line) = self.lines.next_line().await? {
+            if !line.trim().is_empty() {
+                return Ok(Some(TraceMsg::new(line)));
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// One proof packet (or event) per line.
+pub struct JsonlSink {
+    out: Box<dyn AsyncWrite + Unpin + Send>,
+}
+
+impl JsonlSink {
+    pub fn new(writer: impl AsyncWrite + Unpin + Send + 'static) -> Self {
+        JsonlSink { out: Box::new(writer) }
+    }
+
+    pub fn stdout() -> Self {
+        Self::new(tokio::io::stdout())
+    }
+
+    pub async fn append(path: &std::path::Path) -> anyhow::Result<Self> {
+        let file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .await
+            .map_err(|e| anyhow::anyhow!("cannot open {}: {e}", path.display()))?;
+        Ok(Self::new(file))
+    }
+}
+
+impl ProofSink for JsonlSink {
+    async fn send(&mut self, batch: Batch, _commit: Commit) -> anyhow::Result<()> {
+        for packet in &batch.packets {
+            self.out.write_all(packet).await?;
+            self.out.write_all(b"\n").await?;
+        }
+        self.out.flush().await?;
+        Ok(())
+    }
+}
+
+// ------------------------------------------------------------------
+// In memory
+// ------------------------------------------------------------------
+
+/// Messages fed through a channel; exhausted once every sender is dropped.
+pub struct MemorySource {
+    rx: mpsc::UnboundedReceiver<TraceMsg>,
+}
+
+impl MemorySource {
+    pub fn channel() -> (mpsc::UnboundedSender<TraceMsg>, Self) {
+        let (tx, rx) = mpsc::unbounded_channel();
+        (tx, MemorySource { rx })
+    }
+}
+
+impl TraceSource for MemorySource {
+    async fn next(&mut self) -> anyhow::Result<Option<TraceMsg>> {
+        Ok(self.rx.recv().await)
+    }
+}
+
+/// Collects packets and commits, and keeps the latest checkpoint per
+/// asset.
+#[derive(Debug, Default)]
+pub struct MemorySink {
+    pub packets: Vec<Vec<u8>>,
+    pub checkpoints: BTreeMap<String, Snapshot>,
+    /// `Commit::resume` of every batch, in order.
+    pub commits: Vec<BTreeMap<(String, i32), i64>>,
+    /// Set: batches carry no checkpoints, as on the JSONL sinks.
+    pub no_checkpoints: bool,
+}
+
+impl ProofSink for MemorySink {
+    async fn send(&mut self, batch: Batch, commit: Commit) -> anyhow::Result<()> {
+        self.commits.push(commit.resume);
+        self.packets.extend(batch.packets);
+        for snapshot in batch.checkpoints {
+            self.checkpoints.insert(snapshot.asset.clone(), snapshot);
+        }
+        for asset in batch.retired {
+            self.checkpoints.remove(&asset);
+        }
+        Ok(())
+    }
+
+    fn keeps_checkpoints(&self) -> bool {
+        !self.no_checkpoints
+    }
+
+    async fn restore(&mut self) -> anyhow::Result<Vec<Snapshot>> {
+        Ok(self.checkpoints.values().cloned().collect())
+    }
+}
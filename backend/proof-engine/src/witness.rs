@@ -0,0 +1,167 @@
+//! Counterexample witness for a FAIL verdict.
+//!
+//! `explain` re-runs `eval_truth` top-down on a violated window and keeps
+//! descending while a single child accounts for the failure: the failing
+//! conjunct of an `And`, the failing drop of a `WindowAll`.  Where the
+//! failure needs several children at once (`Or`, `Implies`, `ImplWithin`,
+//! `Not`) it stops and blames that node.  The witness carries the blamed
+//! sub-formula, the tags it reads and just the samples it looked at.
+//!
+//! A sub-formula "fails" when it is not proved, so a verdict that missing
+//! readings made FAIL (or INCONCLUSIVE) is explained the same way; the
+//! missing tags are simply absent from the witness samples.
+
+use crate::dsl::{drop_newest, eval_truth, Missing, Prop, Sample, Truth, Var};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Witness {
+    /// Pre-order index of the failing sub-formula (`Prop::node`).
+    pub node: usize,
+    /// The failing sub-formula in DSL syntax.
+    pub formula: String,
+    pub tags: Vec<String>,
+    /// Samples the sub-formula read, newest first.
+    pub samples: Vec<WitnessSample>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct WitnessSample {
+    /// Position in the window: 0 is the tick that raised the verdict.
+    pub age: usize,
+    pub ts_ns: i64,
+    /// Readings of the witness tags; absent tags are left out.
+    pub values: BTreeMap<String, f64>,
+}
+
+struct Blame {
+    node: usize,
+    ages: BTreeSet<usize>,
+    tags: BTreeSet<Var>,
+}
+
+fn vars(p: &Prop, out: &mut BTreeSet<Var>) {
+    use Prop::*;
+    match p {
+        Le(v, _) | Ge(v, _) | Lt(v, _) | Gt(v, _) | Eq(v, ..) | Ne(v, ..) | RateBound(v, _) => {
+            out.insert(*v);
+        }
+        WindowAll(_, q) | Not(q) => vars(q, out),
+        ImplWithin(a, b, _) | And(a, b) | Or(a, b) | Implies(a, b) => {
+            vars(a, out);
+            vars(b, out);
+        }
+    }
+}
+
+/// Blame `p` as a whole: every sample and tag it reads.
+fn whole(p: &Prop, at: usize, idx: usize) -> Blame {
+    let mut tags = BTreeSet::new();
+    vars(p, &mut tags);
+    Blame { node: idx, ages: (at..at + p.history()).collect(), tags }
+}
+
+/// Why `p` fails on the window dropping `at` newest samples; `idx` is the
+/// pre-order index of `p` in the root property.
+fn blame(p: &Prop, trace: (&[Sample], &[i64]), missing: Missing, at: usize, idx: usize) -> Blame {
+    use Prop::*;
+    let fails =
+        |q: &Prop, at: usize| eval_truth(q, drop_newest(trace.0, at), drop_newest(trace.1, at), missing) != Truth::True;
+    match p {
+        WindowAll(k, q) => {
+            // Same drops `eval_truth` visits: stop at [] unless it is the last.
+            let i = (0..=*k)
+                .find(|&i| (i == *k || at + i < trace.0.len()) && fails(q, at + i))
+                .unwrap_or(0);
+            blame(q, trace, missing, at + i, idx + 1)
+        }
+        And(a, _) if fails(a, at) => blame(a, trace, missing, at, idx + 1),
+        And(a, b) => blame(b, trace, missing, at, idx + 1 + a.size()),
+        Or(a, b) => {
+            let (l, r) = (blame(a, trace, missing, at, idx + 1), blame(b, trace, missing, at, idx + 1 + a.size()));
+            Blame { node: idx, ages: &l.ages | &r.ages, tags: &l.tags | &r.tags }
+        }
+        Implies(a, b) => {
+            // `a` holds and `b` fails: keep `a` whole, localise `b`.
+            let (l, r) = (whole(a, at, idx + 1), blame(b, trace, missing, at, idx + 1 + a.size()));
+            Blame { node: idx, ages: &l.ages | &r.ages, tags: &l.tags | &r.tags }
+        }
+        _ => whole(p, at, idx),
+    }
+}
+
+/// Witness for `p` on `window` (newest-first, with its timestamps in ns)
+/// under the `missing` policy, or `None` if `p` holds there.
+pub fn explain(p: &Prop, window: &[Sample], stamps_ns: &[i64], missing: Missing) -> Option<Witness> {
+    debug_assert_eq!(window.len(), stamps_ns.len());
+    if eval_truth(p, window, stamps_ns, missing) == Truth::True {
+        return None;
+    }
+    let b = blame(p, (window, stamps_ns), missing, 0, 0);
+    let samples = b
+        .ages
+        .iter()
+        .filter(|&&age| age < window.len())
+        .map(|&age| WitnessSample {
+            age,
+            ts_ns: stamps_ns[age],
+            values: b
+                .tags
+                .iter()
+                .filter_map(|v| window[age].get(v).map(|x| (v.name().to_string(), *x)))
+                .collect(),
+        })
+        .collect();
+    Some(Witness {
+        node: b.node,
+        formula: p.node(b.node).expect("blamed node exists").to_string(),
+        tags: b.tags.iter().map(|v| v.name().to_string()).collect(),
+        samples,
+    })
+}
+
+// ---------------------------
+// Unit tests
+// ---------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_prop;
+    use std::collections::HashMap;
+
+    /// Newest-first window of (P, T) readings stamped 0, 1, 2, ... oldest last.
+    fn window(readings: &[(f64, f64)]) -> (Vec<Sample>, Vec<i64>) {
+        let samples = readings.iter().map(|&(p, t)| HashMap::from([(Var::P, p), (Var::T, t)])).collect();
+        let stamps = (0..readings.len() as i64).rev().collect();
+        (samples, stamps)
+    }
+
+    #[test]
+    fn localises_to_the_failing_atom_and_sample() {
+        let p = parse_prop("always[2](P <= 120 && T <= 80)").unwrap();
+        let (w, ts) = window(&[(100.0, 20.0), (100.0, 95.0), (100.0, 20.0)]);
+        let wit = explain(&p, &w, &ts, Missing::Unknown).unwrap();
+        assert_eq!(wit.formula, "T <= 80");
+        assert_eq!(p.node(wit.node), Some(&Prop::Le(Var::T, 80.0)));
+        assert_eq!(wit.tags, ["T"]);
+        assert_eq!(
+            wit.samples,
+            [WitnessSample { age: 1, ts_ns: 1, values: BTreeMap::from([("T".to_string(), 95.0)]) }]
+        );
+
+        let (ok, ts) = window(&[(100.0, 20.0)]);
+        assert_eq!(explain(&p, &ok, &ts, Missing::Unknown), None);
+    }
+
+    #[test]
+    fn blames_whole_node_when_no_single_child_is_at_fault() {
+        let p = parse_prop("P > 200 -> |dT| <= 5").unwrap();
+        let (w, ts) = window(&[(250.0, 40.0), (100.0, 20.0), (90.0, 20.0)]);
+        let wit = explain(&p, &w, &ts, Missing::Unknown).unwrap();
+        assert_eq!(wit.node, 0);
+        assert_eq!(wit.tags, ["P", "T"]);
+        assert_eq!(wit.samples.iter().map(|s| s.age).collect::<Vec<_>>(), [0, 1]);
+        assert_eq!(wit.samples[1].values, BTreeMap::from([("P".into(), 100.0), ("T".into(), 20.0)]));
+    }
+}
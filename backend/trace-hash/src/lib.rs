@@ -0,0 +1,168 @@
+//! Canonical trace hash (`trace_hash` in proof packets), version 1.
+//!
+//! The hash is BLAKE3 over a byte string that depends only on the readings
+//! and their timestamps – never on map iteration order, tag ids or the
+//! process that computed it – so the ledger and external auditors can
+//! recompute it from raw data.
+//!
+//! ```text
+//! trace    = name(DOMAIN)
+//!            ‖ u32(#tags) ‖ name*            -- union of tag names, sorted bytewise
+//!            ‖ u32(#samples) ‖ sample*       -- oldest first
+//! name     = u32(len) ‖ utf-8 bytes
+//! sample   = 'S' ‖ i64(ts_ns) ‖ value*       -- one value per tag in the header
+//! value    = 0x00                            -- tag absent from this sample
+//!          | 0x01 ‖ f64 bits                 -- number; -0.0 is written as +0.0
+//!          | 0x02                            -- NaN (any payload)
+//! ```
+//!
+//! All integers are little-endian.  `ts_ns` is the sample timestamp in
+//! nanoseconds since the Unix epoch.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Domain separator; changes with every incompatible revision.
+pub const DOMAIN: &str = "sentinelops/trace-hash/v1";
+
+const SAMPLE: u8 = b'S';
+const ABSENT: u8 = 0x00;
+const NUMBER: u8 = 0x01;
+const NAN: u8 = 0x02;
+
+/// One timestamped sample, as carried in raw trace files.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct TraceSample {
+    pub ts_ns: i64,
+    pub values: BTreeMap<String, f64>,
+}
+
+impl TraceSample {
+    pub fn new(ts_ns: i64, values: impl IntoIterator<Item = (impl Into<String>, f64)>) -> Self {
+        TraceSample { ts_ns, values: values.into_iter().map(|(k, v)| (k.into(), v)).collect() }
+    }
+}
+
+fn put_u32(out: &mut Vec<u8>, n: usize) {
+    let n = u32::try_from(n).expect("trace component longer than u32::MAX");
+    out.extend_from_slice(&n.to_le_bytes());
+}
+
+fn put_str(out: &mut Vec<u8>, s: &str) {
+    put_u32(out, s.len());
+    out.extend_from_slice(s.as_bytes());
+}
+
+/// Canonical byte string of a trace given oldest-first.
+pub fn encode(samples: &[TraceSample]) -> Vec<u8> {
+    let tags: BTreeSet<&str> = samples.iter().flat_map(|s| s.values.keys().map(String::as_str)).collect();
+    let mut out = Vec::with_capacity(64 + samples.len() * (9 + tags.len() * 9));
+    put_str(&mut out, DOMAIN);
+    put_u32(&mut out, tags.len());
+    for tag in &tags {
+        put_str(&mut out, tag);
+    }
+    put_u32(&mut out, samples.len());
+    for s in samples {
+        out.push(SAMPLE);
+        out.extend_from_slice(&s.ts_ns.to_le_bytes());
+        for tag in &tags {
+            match s.values.get(*tag) {
+                None => out.push(ABSENT),
+                Some(x) if x.is_nan() => out.push(NAN),
+                Some(x) => {
+                    out.push(NUMBER);
+                    // `+ 0.0` folds -0.0 into +0.0 and leaves everything else alone
+                    out.extend_from_slice(&(x + 0.0).to_bits().to_le_bytes());
+                }
+            }
+        }
+    }
+    out
+}
+
+/// Trace hash of samples given oldest-first.
+pub fn hash(samples: &[TraceSample]) -> [u8; 32] {
+    *blake3::hash(&encode(samples)).as_bytes()
+}
+
+/// `hash` as lowercase hex, the form carried in proof packets.
+pub fn hash_hex(samples: &[TraceSample]) -> String {
+    hex::encode(hash(samples))
+}
+
+// ---------------------------
+// Unit tests
+// ---------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trace() -> Vec<TraceSample> {
+        vec![
+            TraceSample::new(1_000, [("P", 100.0), ("T", 20.0)]),
+            TraceSample::new(2_000, [("P", 101.5)]),
+        ]
+    }
+
+    #[test]
+    fn golden_vector() {
+        // Pinned so independent implementations can check themselves.
+        let bytes = encode(&trace());
+        let mut want = 25u32.to_le_bytes().to_vec();
+        want.extend_from_slice(DOMAIN.as_bytes());
+        want.extend_from_slice(&2u32.to_le_bytes());
+        want.extend_from_slice(&[1, 0, 0, 0, b'P', 1, 0, 0, 0, b'T']);
+        want.extend_from_slice(&2u32.to_le_bytes());
+        want.push(b'S');
+        want.extend_from_slice(&1_000i64.to_le_bytes());
+        want.push(1);
+        want.extend_from_slice(&100.0f64.to_bits().to_le_bytes());
+        want.push(1);
+        want.extend_from_slice(&20.0f64.to_bits().to_le_bytes());
+        want.push(b'S');
+        want.extend_from_slice(&2_000i64.to_le_bytes());
+        want.push(1);
+        want.extend_from_slice(&101.5f64.to_bits().to_le_bytes());
+        want.push(0);
+        assert_eq!(bytes, want);
+        assert_eq!(hash_hex(&trace()), blake3::hash(&want).to_hex().as_str());
+        assert_eq!(hash_hex(&trace()), "184b7d0c47abec8eb9ffca4f489681031992ca3970fa6c6b2d59cb358d3feafe");
+    }
+
+    #[test]
+    fn insensitive_to_map_order_but_not_to_data() {
+        let h = hash(&trace());
+        let mut reordered = trace();
+        reordered[0] = TraceSample::new(1_000, [("T", 20.0), ("P", 100.0)]);
+        assert_eq!(hash(&reordered), h);
+
+        let mut later = trace();
+        later[1].ts_ns += 1;
+        assert_ne!(hash(&later), h);
+
+        let mut zero = trace();
+        zero[1].values.insert("T".into(), 0.0);
+        assert_ne!(hash(&zero), h, "absent must differ from 0.0");
+
+        let mut swapped = trace();
+        swapped.reverse();
+        assert_ne!(hash(&swapped), h);
+    }
+
+    #[test]
+    fn canonical_floats() {
+        let nan_a = f64::from_bits(0x7ff8_0000_0000_0001);
+        let a = [TraceSample::new(0, [("P", -0.0), ("T", nan_a)])];
+        let b = [TraceSample::new(0, [("P", 0.0), ("T", f64::NAN)])];
+        assert_eq!(hash(&a), hash(&b));
+        let inf = [TraceSample::new(0, [("P", f64::INFINITY), ("T", f64::NAN)])];
+        assert_ne!(hash(&inf), hash(&b));
+    }
+
+    #[test]
+    fn raw_sample_json() {
+        let s: TraceSample = serde_json::from_str(r#"{"ts_ns":5,"values":{"P":1.0}}"#).unwrap();
+        assert_eq!(s, TraceSample::new(5, [("P", 1.0)]));
+    }
+}
@@ -0,0 +1,191 @@
+//! Independent re-check of a proof packet.
+//!
+//! Given the property, the raw samples (oldest first, the last one being
+//! the tick that emitted the packet) and the packet bytes as published,
+//! `verify` recomputes `trace_hash` and `cert_hash` over the judged window,
+//! checks the packet's `start_ts`/`end_ts` against that window and its
+//! `pack_version` against the pack the property came from, replays the
+//! samples through the proof-engine in the packet's `verdict_source` mode
+//! under the property's missing-data policy, and optionally proves the
+//! packet into an anchored Merkle root.
+//!
+//! In either mode a violation stays in force while its tick is inside the
+//! horizon, so the replay is exact once the trace starts a full horizon
+//! before the oldest judged sample.
+
+use anyhow::Context as _;
+use ledger::batch::ProofPacket;
+use ledger::merkle::{self, Hash};
+use proof_engine::cert;
+use proof_engine::dsl::{Missing, Prop, Sample, Truth, Var};
+use proof_engine::engine::{Engine, EngineMode};
+use proof_engine::monitor::{solver_context, Horizon};
+use sentinel_trace_hash::TraceSample;
+
+/// Anchored batch the packet should belong to.
+pub struct Anchor {
+    /// All tree nodes as stored in `merkle_batches.dag`.
+    pub dag: Vec<Hash>,
+    /// Root as anchored on chain.
+    pub root: Hash,
+}
+
+/// Outcome of one check.
+#[derive(Debug, PartialEq)]
+pub struct Check {
+    pub name: &'static str,
+    pub ok: bool,
+    pub detail: String,
+}
+
+impl Check {
+    fn compare(name: &'static str, got: &str, claimed: &str) -> Check {
+        let detail = if got == claimed { got.to_string() } else { format!("recomputed {got}, packet says {claimed}") };
+        Check { name, ok: got == claimed, detail }
+    }
+}
+
+/// Unix seconds as the proof-engine writes them into packets.
+fn unix_secs(ts_ns: i64) -> f64 {
+    ts_ns.div_euclid(1_000_000_000) as f64 + ts_ns.rem_euclid(1_000_000_000) as f64 / 1e9
+}
+
+/// Tags the registry rejects are left out, as the proof-engine does.
+fn to_sample(s: &TraceSample) -> Sample {
+    s.values.iter().filter_map(|(k, v)| Some((Var::named(k)?, *v))).collect()
+}
+
+/// `pack_version` is the version of the pack `prop` was taken from, if any;
+/// the packet must name the same one.
+pub fn verify(
+    prop: &Prop,
+    horizon: Horizon,
+    missing: Missing,
+    pack_version: Option<&str>,
+    samples: &[TraceSample],
+    packet_bytes: &[u8],
+    anchor: Option<&Anchor>,
+) -> anyhow::Result<Vec<Check>> {
+    let packet: ProofPacket = serde_json::from_slice(packet_bytes).context("packet is not a proof packet")?;
+    anyhow::ensure!(!samples.is_empty(), "trace is empty");
+    let mode = match packet.verdict_source.as_deref() {
+        Some(s) => s.parse::<EngineMode>().map_err(anyhow::Error::msg)?,
+        None => EngineMode::Rust,
+    };
+    let mut checks = Vec::new();
+
+    let stamps: Vec<i64> = samples.iter().rev().map(|s| s.ts_ns).collect();
+    let judged = &samples[samples.len() - horizon.window_len(&stamps)..];
+    checks.push(Check::compare("trace_hash", &sentinel_trace_hash::hash_hex(judged), &packet.trace_hash));
+    let span = |start: f64, end: f64| format!("{start}..{end}");
+    checks.push(Check::compare(
+        "window",
+        &span(unix_secs(judged[0].ts_ns), unix_secs(judged[judged.len() - 1].ts_ns)),
+        &span(packet.start_ts, packet.end_ts),
+    ));
+
+    let ctx = solver_context();
+    let mut engine = Engine::new(&ctx, mode, [(prop.clone(), horizon, missing)])?;
+    let mut truth = Truth::True;
+    for s in samples {
+        truth = engine.tick(s.ts_ns, to_sample(s))[0].truth;
+    }
+    let cert_hash = cert::cert_hash(prop, engine.window(), engine.timestamps())?;
+    checks.push(Check::compare("cert_hash", &cert_hash, &packet.cert_hash));
+    checks.push(Check::compare("verdict", truth.verdict(), &packet.verdict));
+    if let Some(version) = pack_version {
+        checks.push(match &packet.pack_version {
+            Some(claimed) => Check::compare("pack", version, claimed),
+            None => Check { name: "pack", ok: false, detail: "packet names no pack_version".into() },
+        });
+    }
+
+    if let Some(anchor) = anchor {
+        let leaf = merkle::packet_hash(packet_bytes);
+        let included = merkle::levels(&anchor.dag)
+            .and_then(|levels| levels[0].iter().position(|l| *l == leaf))
+            .and_then(|i| merkle::inclusion_proof(&anchor.dag, i).map(|proof| (i, proof)));
+        checks.push(match included {
+            Some((i, proof)) if merkle::verify_inclusion(leaf, &proof, anchor.root) => Check {
+                name: "merkle",
+                ok: true,
+                detail: format!("leaf {i} of root {}", hex::encode(anchor.root)),
+            },
+            Some(_) => Check { name: "merkle", ok: false, detail: "batch does not hash to the anchored root".into() },
+            None => Check { name: "merkle", ok: false, detail: "packet is not a leaf of the batch".into() },
+        });
+    }
+    Ok(checks)
+}
+
+// ---------------------------
+// Unit tests
+// ---------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proof_engine::parser::parse_prop;
+
+    fn trace() -> Vec<TraceSample> {
+        [100.0, 110.0, 125.0]
+            .iter()
+            .enumerate()
+            .map(|(i, p)| TraceSample::new(i as i64 * 1_000_000_000, [("P", *p), ("T", 20.0)]))
+            .collect()
+    }
+
+    /// Packet as proof-engine would emit it for the last sample.
+    fn packet(prop: &Prop, horizon: usize, samples: &[TraceSample], source: &str) -> Vec<u8> {
+        let judged = &samples[samples.len() - horizon..];
+        let window: Vec<Sample> = judged.iter().rev().map(to_sample).collect();
+        let stamps: Vec<i64> = judged.iter().rev().map(|s| s.ts_ns).collect();
+        let verdict = if proof_engine::dsl::eval_prop(prop, &window) { "PASS" } else { "FAIL" };
+        serde_json::to_vec(&serde_json::json!({
+            "property_id": "seg.max_pressure",
+            "pack_version": "2025.07",
+            "start_ts": unix_secs(judged[0].ts_ns),
+            "end_ts": unix_secs(judged[horizon - 1].ts_ns),
+            "trace_hash": sentinel_trace_hash::hash_hex(judged),
+            "cert_hash": cert::cert_hash(prop, &window, &stamps).unwrap(),
+            "verdict": verdict,
+            "verdict_source": source,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn accepts_honest_packet_and_anchor() {
+        let prop = parse_prop("P <= 120").unwrap();
+        let bytes = packet(&prop, 2, &trace(), "sat");
+        let leaves = [merkle::packet_hash(b"other"), merkle::packet_hash(&bytes), merkle::packet_hash(b"third")];
+        let (root, dag) = merkle::build_merkle(&leaves);
+        let anchor = Anchor { dag, root };
+        let checks = verify(&prop, Horizon::Samples(2), Missing::Unknown, Some("2025.07"), &trace(), &bytes, Some(&anchor))
+            .unwrap();
+        assert!(checks.iter().all(|c| c.ok), "{checks:?}");
+        assert_eq!(checks.len(), 6);
+    }
+
+    #[test]
+    fn flags_tampering() {
+        let prop = parse_prop("P <= 120").unwrap();
+        let bytes = packet(&prop, 2, &trace(), "rust");
+
+        let mut edited = trace();
+        edited[2].values.insert("P".into(), 119.0);
+        let failed = |checks: Vec<Check>| checks.into_iter().filter(|c| !c.ok).map(|c| c.name).collect::<Vec<_>>();
+        let checks = verify(&prop, Horizon::Samples(2), Missing::Unknown, None, &edited, &bytes, None).unwrap();
+        assert_eq!(failed(checks), ["trace_hash", "cert_hash", "verdict"]);
+
+        // same readings a second later, judged under another pack
+        let mut shifted = trace();
+        shifted.iter_mut().for_each(|s| s.ts_ns += 1_000_000_000);
+        let checks = verify(&prop, Horizon::Samples(2), Missing::Unknown, Some("2025.08"), &shifted, &bytes, None).unwrap();
+        assert_eq!(failed(checks), ["trace_hash", "window", "cert_hash", "pack"]);
+
+        let (root, dag) = merkle::build_merkle(&[merkle::packet_hash(b"other")]);
+        let anchor = Anchor { dag, root };
+        let checks = verify(&prop, Horizon::Samples(2), Missing::Unknown, None, &trace(), &bytes, Some(&anchor)).unwrap();
+        assert!(!checks.last().unwrap().ok);
+    }
+}
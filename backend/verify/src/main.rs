@@ -0,0 +1,105 @@
+//! CLI:
+//!   sentinel-verify --packet packet.json --trace trace.jsonl
+//!                   (--pack packs/default.toml [--property ID] | --expr EXPR --horizon N)
+//!                   [--missing POLICY] [--dag dag.hex --root HEX]
+//!
+//! * `--packet` – the packet exactly as consumed from the proof topic.
+//! * `--trace`  – raw samples of the packet's `asset`, oldest first, ending
+//!   with the tick that emitted the packet: a JSON array or one object per
+//!   line, each
+//!   `{"ts_ns": 1688145051000000000, "values": {"P": 75.2, "T": 24.1}}`.
+//! * `--pack`   – property pack; the property defaults to the packet's
+//!   `property_id`, and the packet's `pack_version` must be the pack's.
+//!   `--expr`/`--horizon` give the property inline instead, with the
+//!   horizon a sample count (`6`) or a duration (`30s`).
+//! * `--missing` – missing-data policy (`unknown`, `hold_last`, `violation`);
+//!   defaults to the pack's, or `unknown` with `--expr`.
+//! * `--dag` / `--root` – hex dump of `merkle_batches.dag` for the batch and
+//!   the root anchored on chain.
+//!
+//! Exits with status 1 if any check fails.
+
+use anyhow::Context;
+use ledger::merkle::Hash;
+use proof_engine::dsl::Missing;
+use proof_engine::monitor::Horizon;
+use proof_engine::pack::PropertyPack;
+use proof_engine::parser::parse_prop;
+use sentinel_trace_hash::TraceSample;
+use sentinel_verify::{verify, Anchor};
+
+fn read(path: &str) -> anyhow::Result<Vec<u8>> {
+    std::fs::read(path).with_context(|| format!("cannot read {path}"))
+}
+
+fn parse_samples(src: &str) -> anyhow::Result<Vec<TraceSample>> {
+    if src.trim_start().starts_with('[') {
+        return Ok(serde_json::from_str(src)?);
+    }
+    src.lines()
+        .filter(|l| !l.trim().is_empty())
+        .enumerate()
+        .map(|(i, l)| serde_json::from_str(l).with_context(|| format!("trace line {}", i + 1)))
+        .collect()
+}
+
+fn parse_hash(hex_str: &str) -> anyhow::Result<Hash> {
+    let bytes = hex::decode(hex_str.trim().trim_start_matches("0x").trim_start_matches("\\x"))?;
+    bytes.try_into().map_err(|_| anyhow::anyhow!("expected a 32-byte hash"))
+}
+
+fn main() -> anyhow::Result<()> {
+    let mut args = pico_args::Arguments::from_env();
+    let packet_path: String = args.value_from_str("--packet")?;
+    let trace_path: String = args.value_from_str("--trace")?;
+    let pack_path: Option<String> = args.opt_value_from_str("--pack")?;
+    let property: Option<String> = args.opt_value_from_str("--property")?;
+    let expr: Option<String> = args.opt_value_from_str("--expr")?;
+    let horizon: Option<Horizon> = args.opt_value_from_str("--horizon")?;
+    let missing: Option<Missing> = args.opt_value_from_str("--missing")?;
+    let dag_path: Option<String> = args.opt_value_from_str("--dag")?;
+    let root: Option<String> = args.opt_value_from_str("--root")?;
+
+    let packet = read(&packet_path)?;
+    let samples = parse_samples(&String::from_utf8(read(&trace_path)?)?)?;
+
+    let (prop, horizon, missing, version) = match (pack_path, expr) {
+        (Some(path), None) => {
+            let pack = PropertyPack::load(&path, horizon.unwrap_or(Horizon::Samples(6)))?;
+            let id = match property {
+                Some(id) => id,
+                None => serde_json::from_slice::<serde_json::Value>(&packet)?["property_id"]
+                    .as_str()
+                    .context("packet has no property_id")?
+                    .to_string(),
+            };
+            let p = pack.index_of(&id).map(|i| &pack.properties[i]).with_context(|| format!("{id} not in {path}"))?;
+            (p.prop.clone(), p.horizon, missing.unwrap_or(p.missing), Some(pack.version.clone()))
+        }
+        (None, Some(expr)) => {
+            let prop = parse_prop(&expr).map_err(|e| anyhow::anyhow!(e.render(&expr)))?;
+            (prop, horizon.context("--expr needs --horizon")?, missing.unwrap_or_default(), None)
+        }
+        _ => anyhow::bail!("give either --pack or --expr"),
+    };
+
+    let anchor = match (dag_path, root) {
+        (Some(dag), Some(root)) => {
+            let bytes = hex::decode(String::from_utf8(read(&dag)?)?.trim().trim_start_matches("\\x"))?;
+            anyhow::ensure!(bytes.len() % 32 == 0, "dag length is not a multiple of 32");
+            let dag = bytes.chunks(32).map(|c| c.try_into().unwrap()).collect();
+            Some(Anchor { dag, root: parse_hash(&root)? })
+        }
+        (None, None) => None,
+        _ => anyhow::bail!("--dag and --root go together"),
+    };
+
+    let checks = verify(&prop, horizon, missing, version.as_deref(), &samples, &packet, anchor.as_ref())?;
+    for c in &checks {
+        println!("{:<10} {}  {}", c.name, if c.ok { "ok  " } else { "FAIL" }, c.detail);
+    }
+    if checks.iter().any(|c| !c.ok) {
+        std::process::exit(1);
+    }
+    Ok(())
+}
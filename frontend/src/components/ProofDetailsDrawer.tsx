@@ -16,6 +16,14 @@ interface Proof {
   validator: string;
 }
 
+/** Counterexample attached to FAIL packets by the proof-engine. */
+interface Witness {
+  node: number;
+  formula: string;
+  tags: string[];
+  samples: { age: number; ts_ns: number; values: Record<string, number> }[];
+}
+
 interface ProofDetailsDrawerProps {
   proof: Proof | null;
   open: boolean;
@@ -38,6 +46,8 @@ export const ProofDetailsDrawer = ({ proof, open, onClose }: ProofDetailsDrawerP
 
   if (!proof) return null;
 
+  const witness: Witness | undefined = proof.metadata?.witness;
+
   return (
     <Drawer open={open} onOpenChange={onClose}>
       <DrawerContent className="max-h-[80vh]">
@@ -95,6 +105,43 @@ export const ProofDetailsDrawer = ({ proof, open, onClose }: ProofDetailsDrawerP
               </div>
             </div>
 
+            {/* Counterexample */}
+            {witness && (
+              <div className="space-y-2">
+                <h3 className="font-mono text-sm font-semibold text-muted-foreground uppercase">
+                  Counterexample
+                </h3>
+                <p className="text-sm">
+                  Failing sub-formula:{' '}
+                  <code className="font-mono bg-muted rounded px-1">{witness.formula}</code>
+                </p>
+                <div className="bg-muted rounded-lg p-4 overflow-auto">
+                  <table className="text-xs font-mono w-full">
+                    <thead>
+                      <tr className="text-left text-muted-foreground">
+                        <th className="pr-4">Age</th>
+                        <th className="pr-4">Time</th>
+                        {witness.tags.map((tag) => (
+                          <th key={tag} className="pr-4">{tag}</th>
+                        ))}
+                      </tr>
+                    </thead>
+                    <tbody>
+                      {witness.samples.map((sample) => (
+                        <tr key={sample.age}>
+                          <td className="pr-4">{sample.age}</td>
+                          <td className="pr-4">{new Date(sample.ts_ns / 1e6).toLocaleString()}</td>
+                          {witness.tags.map((tag) => (
+                            <td key={tag} className="pr-4">{sample.values[tag] ?? '–'}</td>
+                          ))}
+                        </tr>
+                      ))}
+                    </tbody>
+                  </table>
+                </div>
+              </div>
+            )}
+
             {/* Full JSON */}
             <div className="space-y-2">
               <h3 className="font-mono text-sm font-semibold text-muted-foreground uppercase">
@@ -25,7 +25,13 @@ const mockProofs = [
     metadata: {
       propertyId: 'PROP-002',
       startTs: new Date(Date.now() - 5400000).toISOString(),
-      endTs: new Date(Date.now() - 1800000).toISOString()
+      endTs: new Date(Date.now() - 1800000).toISOString(),
+      witness: {
+        node: 1,
+        formula: 'P <= 120',
+        tags: ['P'],
+        samples: [{ age: 0, ts_ns: (Date.now() - 1800000) * 1e6, values: { P: 131.4 } }]
+      }
     },
     hash: 'b2c3d4e5f6789012345678901234567890abcde',
     size: 2048,